@@ -20,6 +20,18 @@ namespace Kelp2d
         }
 
 
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "compact_atlas")]
+        public static extern FFIError CompactAtlas(out AtlasCompaction out_report);
+
+        public static void CompactAtlas_checked(out AtlasCompaction out_report)
+        {
+            var rval = CompactAtlas(out out_report);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
         [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "create_empty_texture")]
         public static extern FFIError CreateEmptyTexture(uint width, uint height, out ulong out_id);
 
@@ -32,6 +44,118 @@ namespace Kelp2d
             }
         }
 
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "create_indexed_texture")]
+        public static extern FFIError CreateIndexedTexture(uint width, uint height, Sliceu8 indices, out ulong out_id);
+
+        public static void CreateIndexedTexture(uint width, uint height, byte[] indices, out ulong out_id)
+        {
+            unsafe
+            {
+                fixed (void* ptr_indices = indices)
+                {
+                    var indices_slice = new Sliceu8(new IntPtr(ptr_indices), (ulong) indices.Length);
+                    var rval = CreateIndexedTexture(width, height, indices_slice, out out_id);;
+                    if (rval != FFIError.Success)
+                    {
+                        throw new InteropException<FFIError>(rval);
+                    }
+                }
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "create_lut")]
+        public static extern FFIError CreateLut(uint size, Sliceu8 data, out ulong out_id);
+
+        public static void CreateLut(uint size, byte[] data, out ulong out_id)
+        {
+            unsafe
+            {
+                fixed (void* ptr_data = data)
+                {
+                    var data_slice = new Sliceu8(new IntPtr(ptr_data), (ulong) data.Length);
+                    var rval = CreateLut(size, data_slice, out out_id);;
+                    if (rval != FFIError.Success)
+                    {
+                        throw new InteropException<FFIError>(rval);
+                    }
+                }
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "create_palette")]
+        public static extern FFIError CreatePalette(Sliceu8 colors, out uint out_id);
+
+        public static void CreatePalette(byte[] colors, out uint out_id)
+        {
+            unsafe
+            {
+                fixed (void* ptr_colors = colors)
+                {
+                    var colors_slice = new Sliceu8(new IntPtr(ptr_colors), (ulong) colors.Length);
+                    var rval = CreatePalette(colors_slice, out out_id);;
+                    if (rval != FFIError.Success)
+                    {
+                        throw new InteropException<FFIError>(rval);
+                    }
+                }
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "create_post_shader")]
+        public static extern FFIError CreatePostShader(string source, out ulong out_id);
+
+        public static void CreatePostShader_checked(string source, out ulong out_id)
+        {
+            var rval = CreatePostShader(source, out out_id);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "create_region")]
+        public static extern FFIError CreateRegion(ulong texture, KelpRect rect, out uint out_id);
+
+        public static void CreateRegion_checked(ulong texture, KelpRect rect, out uint out_id)
+        {
+            var rval = CreateRegion(texture, rect, out out_id);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "create_texture_dynamic")]
+        public static extern FFIError CreateTextureDynamic(uint width, uint height, out ulong out_id);
+
+        public static void CreateTextureDynamic_checked(uint width, uint height, out ulong out_id)
+        {
+            var rval = CreateTextureDynamic(width, height, out out_id);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "create_texture_from_memory")]
+        public static extern FFIError CreateTextureFromMemory(Sliceu8 data, out ulong out_id);
+
+        public static void CreateTextureFromMemory(byte[] data, out ulong out_id)
+        {
+            unsafe
+            {
+                fixed (void* ptr_data = data)
+                {
+                    var data_slice = new Sliceu8(new IntPtr(ptr_data), (ulong) data.Length);
+                    var rval = CreateTextureFromMemory(data_slice, out out_id);;
+                    if (rval != FFIError.Success)
+                    {
+                        throw new InteropException<FFIError>(rval);
+                    }
+                }
+            }
+        }
+
         [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "create_texture_with_data")]
         public static extern FFIError CreateTextureWithData(uint width, uint height, Sliceu8 data, out ulong out_id);
 
@@ -51,6 +175,25 @@ namespace Kelp2d
             }
         }
 
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "create_texture_with_format")]
+        public static extern FFIError CreateTextureWithFormat(uint width, uint height, Sliceu8 data, UploadFormat format, ColorSpace color_space, out ulong out_id);
+
+        public static void CreateTextureWithFormat(uint width, uint height, byte[] data, UploadFormat format, ColorSpace color_space, out ulong out_id)
+        {
+            unsafe
+            {
+                fixed (void* ptr_data = data)
+                {
+                    var data_slice = new Sliceu8(new IntPtr(ptr_data), (ulong) data.Length);
+                    var rval = CreateTextureWithFormat(width, height, data_slice, format, color_space, out out_id);;
+                    if (rval != FFIError.Success)
+                    {
+                        throw new InteropException<FFIError>(rval);
+                    }
+                }
+            }
+        }
+
         [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "initialise")]
         public static extern FFIError Initialise(WindowInfo window, IntPtr imgui_config);
 
@@ -63,6 +206,51 @@ namespace Kelp2d
             }
         }
 
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "load_progress")]
+        public static extern FFIError LoadProgress(out LoadProgress out_progress);
+
+        public static void LoadProgress_checked(out LoadProgress out_progress)
+        {
+            var rval = LoadProgress(out out_progress);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "load_texture_async_from_memory")]
+        public static extern FFIError LoadTextureAsyncFromMemory(Sliceu8 data, out uint out_id);
+
+        public static void LoadTextureAsyncFromMemory(byte[] data, out uint out_id)
+        {
+            unsafe
+            {
+                fixed (void* ptr_data = data)
+                {
+                    var data_slice = new Sliceu8(new IntPtr(ptr_data), (ulong) data.Length);
+                    var rval = LoadTextureAsyncFromMemory(data_slice, out out_id);;
+                    if (rval != FFIError.Success)
+                    {
+                        throw new InteropException<FFIError>(rval);
+                    }
+                }
+            }
+        }
+
+        /// Sets `out_ready` once the texture has loaded, after which `out_id` holds it
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "loaded_texture")]
+        public static extern FFIError LoadedTexture(uint load, out ulong out_id, out bool out_ready);
+
+        /// Sets `out_ready` once the texture has loaded, after which `out_id` holds it
+        public static void LoadedTexture_checked(uint load, out ulong out_id, out bool out_ready)
+        {
+            var rval = LoadedTexture(load, out out_id, out out_ready);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
         [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "present_frame")]
         public static extern FFIError PresentFrame();
 
@@ -75,6 +263,30 @@ namespace Kelp2d
             }
         }
 
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "remove_lut")]
+        public static extern FFIError RemoveLut(ulong lut);
+
+        public static void RemoveLut_checked(ulong lut)
+        {
+            var rval = RemoveLut(lut);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "remove_texture")]
+        public static extern FFIError RemoveTexture(ulong texture);
+
+        public static void RemoveTexture_checked(ulong texture)
+        {
+            var rval = RemoveTexture(texture);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
         [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "render_imgui")]
         public static extern FFIError RenderImgui(IntPtr draw_data);
 
@@ -88,15 +300,15 @@ namespace Kelp2d
         }
 
         [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "render_list")]
-        public static extern FFIError RenderList(ulong target, Camera camera, ref KelpColor clear, SliceInstanceData instances, SliceInstanceBatch batches);
+        public static extern FFIError RenderList(ulong target, Camera camera, ref KelpColor clear, SliceInstanceGPU instances, SliceInstanceBatch batches);
 
-        public static void RenderList(ulong target, Camera camera, ref KelpColor clear, InstanceData[] instances, InstanceBatch[] batches)
+        public static void RenderList(ulong target, Camera camera, ref KelpColor clear, InstanceGPU[] instances, InstanceBatch[] batches)
         {
             unsafe
             {
                 fixed (void* ptr_instances = instances)
                 {
-                    var instances_slice = new SliceInstanceData(new IntPtr(ptr_instances), (ulong) instances.Length);
+                    var instances_slice = new SliceInstanceGPU(new IntPtr(ptr_instances), (ulong) instances.Length);
                     fixed (void* ptr_batches = batches)
                     {
                         var batches_slice = new SliceInstanceBatch(new IntPtr(ptr_batches), (ulong) batches.Length);
@@ -110,6 +322,61 @@ namespace Kelp2d
             }
         }
 
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "set_atlas_padding")]
+        public static extern FFIError SetAtlasPadding(uint padding, bool extrude);
+
+        public static void SetAtlasPadding_checked(uint padding, bool extrude)
+        {
+            var rval = SetAtlasPadding(padding, extrude);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "set_atlas_mipmaps")]
+        public static extern FFIError SetAtlasMipmaps(uint mip_levels);
+
+        public static void SetAtlasMipmaps_checked(uint mip_levels)
+        {
+            var rval = SetAtlasMipmaps(mip_levels);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "set_canvas")]
+        public static extern FFIError SetCanvas(ref CanvasConfig config);
+
+        public static void SetCanvas_checked(ref CanvasConfig config)
+        {
+            var rval = SetCanvas(ref config);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "set_post_effects")]
+        public static extern FFIError SetPostEffects(SlicePostEffectDesc effects);
+
+        public static void SetPostEffects(PostEffectDesc[] effects)
+        {
+            unsafe
+            {
+                fixed (void* ptr_effects = effects)
+                {
+                    var effects_slice = new SlicePostEffectDesc(new IntPtr(ptr_effects), (ulong) effects.Length);
+                    var rval = SetPostEffects(effects_slice);;
+                    if (rval != FFIError.Success)
+                    {
+                        throw new InteropException<FFIError>(rval);
+                    }
+                }
+            }
+        }
+
         [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "set_surface_size")]
         public static extern FFIError SetSurfaceSize(uint width, uint height);
 
@@ -122,6 +389,33 @@ namespace Kelp2d
             }
         }
 
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "set_texture_color_space")]
+        public static extern FFIError SetTextureColorSpace(ulong texture, ColorSpace color_space);
+
+        public static void SetTextureColorSpace_checked(ulong texture, ColorSpace color_space)
+        {
+            var rval = SetTextureColorSpace(texture, color_space);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "set_texture_deduplication")]
+        public static extern FFIError SetTextureDeduplication(bool deduplicate);
+
+        public static void SetTextureDeduplication_checked(bool deduplicate)
+        {
+            var rval = SetTextureDeduplication(deduplicate);;
+            if (rval != FFIError.Success)
+            {
+                throw new InteropException<FFIError>(rval);
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "transform_world")]
+        public static extern WorldMatrix TransformWorld(Transform2D transform, float width, float height);
+
         [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "uninitialise")]
         public static extern FFIError Uninitialise();
 
@@ -134,6 +428,53 @@ namespace Kelp2d
             }
         }
 
+        /// Pass a `bytes_per_row` of 0 for tightly packed rows
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "update_texture_region")]
+        public static extern FFIError UpdateTextureRegion(ulong texture, uint x, uint y, uint width, uint height, Sliceu8 data, uint bytes_per_row);
+
+        /// Pass a `bytes_per_row` of 0 for tightly packed rows
+        public static void UpdateTextureRegion(ulong texture, uint x, uint y, uint width, uint height, byte[] data, uint bytes_per_row)
+        {
+            unsafe
+            {
+                fixed (void* ptr_data = data)
+                {
+                    var data_slice = new Sliceu8(new IntPtr(ptr_data), (ulong) data.Length);
+                    var rval = UpdateTextureRegion(texture, x, y, width, height, data_slice, bytes_per_row);;
+                    if (rval != FFIError.Success)
+                    {
+                        throw new InteropException<FFIError>(rval);
+                    }
+                }
+            }
+        }
+
+        [DllImport(NativeLib, CallingConvention = CallingConvention.Cdecl, EntryPoint = "update_texture_with_format")]
+        public static extern FFIError UpdateTextureWithFormat(ulong texture, Sliceu8 data, UploadFormat format);
+
+        public static void UpdateTextureWithFormat(ulong texture, byte[] data, UploadFormat format)
+        {
+            unsafe
+            {
+                fixed (void* ptr_data = data)
+                {
+                    var data_slice = new Sliceu8(new IntPtr(ptr_data), (ulong) data.Length);
+                    var rval = UpdateTextureWithFormat(texture, data_slice, format);;
+                    if (rval != FFIError.Success)
+                    {
+                        throw new InteropException<FFIError>(rval);
+                    }
+                }
+            }
+        }
+
+    }
+
+    /// Whether uploaded colours are already multiplied by their alpha
+    public enum AlphaMode
+    {
+        Straight = 0,
+        Premultiplied = 1,
     }
 
     public enum BlendMode
@@ -142,6 +483,55 @@ namespace Kelp2d
         ADDITIVE = 1,
     }
 
+    /// How the low resolution canvas is scaled up to fit the window surface
+    public enum CanvasScaling
+    {
+        /// Largest whole number multiple of the canvas size that fits, for pixel-perfect output
+        Integer = 0,
+        /// Largest scale that fits while keeping the aspect ratio
+        Fit = 1,
+    }
+
+    /// Whether a texture holds sRGB encoded colours, or linear values such as normals or masks that are sampled as is
+    public enum ColorSpace
+    {
+        Srgb = 0,
+        Linear = 1,
+    }
+
+    /// The layout of each pixel in uploaded texture data
+    public enum PixelFormat
+    {
+        Rgba8 = 0,
+        Bgra8 = 1,
+        Rgb8 = 2,
+        Gray8 = 3,
+        GrayAlpha8 = 4,
+    }
+
+    /// Which `PostEffect` a `PostEffectDesc` describes
+    public enum PostEffectKind
+    {
+        Blur = 0,
+        Bloom = 1,
+        Vignette = 2,
+        ColorGrade = 3,
+        Scanlines = 4,
+        ChromaticAberration = 5,
+        Lut = 6,
+        Quantize = 7,
+        Custom = 8,
+    }
+
+    /// Which axes a sprite's texture is mirrored along
+    public enum SpriteFlip
+    {
+        None = 0,
+        Horizontal = 1,
+        Vertical = 2,
+        Both = 3,
+    }
+
     public enum WindowType
     {
         Win32 = 0,
@@ -150,6 +540,21 @@ namespace Kelp2d
         AppKit = 3,
     }
 
+    /// How much compacting the atlas helped, measured by the largest free rectangle in any atlas layer
+    [Serializable]
+    [StructLayout(LayoutKind.Sequential)]
+    internal partial struct AtlasCompaction
+    {
+        /// Textures moved to a new position
+        public uint moved;
+        /// Textures left where they were, as their layer no longer fit or moving them would lose mip levels
+        public uint skipped;
+        /// Area of the largest free rectangle before compacting, in pixels
+        public uint largestFreeBefore;
+        /// Area of the largest free rectangle after compacting, in pixels
+        public uint largestFreeAfter;
+    }
+
     [Serializable]
     [StructLayout(LayoutKind.Sequential)]
     internal partial struct Camera
@@ -162,29 +567,73 @@ namespace Kelp2d
         public float scale;
     }
 
+    /// A fixed size virtual canvas that replaces the window surface as the default render target
+    [Serializable]
+    [StructLayout(LayoutKind.Sequential)]
+    internal partial struct CanvasConfig
+    {
+        public uint width;
+        public uint height;
+        public CanvasScaling scaling;
+        /// Colour of the letterbox bars around the scaled canvas
+        public KelpColor bars;
+    }
+
     /// A batch of instances to be added to a render pass
     [Serializable]
     [StructLayout(LayoutKind.Sequential)]
     internal partial struct InstanceBatch
     {
-        public ulong texture;
-        [MarshalAs(UnmanagedType.I1)]
-        public bool smooth;
         public BlendMode blendMode;
         public uint instanceCount;
+        /// The texture drawn from, which is bound instead of the atlas if it is too large to be in it
+        public ulong texture;
     }
 
     [Serializable]
     [StructLayout(LayoutKind.Sequential)]
-    internal partial struct InstanceData
+    internal partial struct InstanceGPU
     {
         public float color0;
         public float color1;
         public float color2;
         public float color3;
-        public Transform source;
-        public Transform world0;
-        public Transform world1;
+        public float mode0;
+        public float mode1;
+        public float mode2;
+        public float mode3;
+        public float sampling0;
+        public float sampling1;
+        public float sampling2;
+        public float sampling3;
+        public float effect0;
+        public float effect1;
+        public float effect2;
+        public float effect3;
+        public float effectColor10;
+        public float effectColor11;
+        public float effectColor12;
+        public float effectColor13;
+        public float effectColor20;
+        public float effectColor21;
+        public float effectColor22;
+        public float effectColor23;
+        public float bounds0;
+        public float bounds1;
+        public float bounds2;
+        public float bounds3;
+        public float sourceTrans0;
+        public float sourceTrans1;
+        public float sourceScale0;
+        public float sourceScale1;
+        public float worldCol10;
+        public float worldCol11;
+        public float worldCol20;
+        public float worldCol21;
+        public float worldTrans0;
+        public float worldTrans1;
+        public float textureInfo0;
+        public float textureInfo1;
     }
 
     [Serializable]
@@ -197,17 +646,73 @@ namespace Kelp2d
         public float a;
     }
 
+    /// A rectangle in pixels, from its top left corner
     [Serializable]
     [StructLayout(LayoutKind.Sequential)]
-    internal partial struct Transform
+    internal partial struct KelpRect
     {
-        public float renderX;
-        public float renderY;
+        public float x;
+        public float y;
+        public float width;
+        public float height;
+    }
+
+    /// Progress of background texture loads, for loading screens
+    [Serializable]
+    [StructLayout(LayoutKind.Sequential)]
+    internal partial struct LoadProgress
+    {
+        public uint loaded;
+        public uint total;
+    }
+
+    /// An FFI safe `PostEffect`, turned into one with `Kelp::post_effect_from_desc`. The parameters are the effect's
+    /// numeric fields in the order they are declared, with the dither mode of `Quantize` as its number, and `id` is the
+    /// LUT, palette or shader the effect uses.
+    [Serializable]
+    [StructLayout(LayoutKind.Sequential)]
+    internal partial struct PostEffectDesc
+    {
+        public PostEffectKind kind;
+        public ulong id;
+        public float params0;
+        public float params1;
+        public float params2;
+        public float params3;
+        public float params4;
+        public float params5;
+        public float params6;
+        public float params7;
+    }
+
+    /// A 2D transform for sprites, applied as flip and scale, then skew, then rotation around the origin
+    [Serializable]
+    [StructLayout(LayoutKind.Sequential)]
+    internal partial struct Transform2D
+    {
+        public float x;
+        public float y;
+        /// Clockwise, in radians
+        public float rotation;
         public float scaleX;
         public float scaleY;
-        public float rotation;
+        /// The pivot in pixels from the top left of the sprite, which is placed at the position
         public float originX;
         public float originY;
+        /// Shear angles in radians, of the horizontal and vertical axes
+        public float skewX;
+        public float skewY;
+        /// Mirrors the sprite around its origin
+        public SpriteFlip flip;
+    }
+
+    /// How uploaded texture data is laid out, converted to straight alpha RGBA on upload
+    [Serializable]
+    [StructLayout(LayoutKind.Sequential)]
+    internal partial struct UploadFormat
+    {
+        public PixelFormat pixels;
+        public AlphaMode alpha;
     }
 
     [Serializable]
@@ -221,6 +726,19 @@ namespace Kelp2d
         public uint height;
     }
 
+    /// The world matrix of an instance, as its two columns and translation
+    [Serializable]
+    [StructLayout(LayoutKind.Sequential)]
+    internal partial struct WorldMatrix
+    {
+        public float col1X;
+        public float col1Y;
+        public float col2X;
+        public float col2Y;
+        public float transX;
+        public float transY;
+    }
+
     /// The main return type for unit returning functions with error handling
     public enum FFIError
     {
@@ -230,12 +748,39 @@ namespace Kelp2d
         NoCurrentFrame = 100,
         SwapchainError = 101,
         InvalidTextureId = 102,
-        InvalidBindGroupId = 103,
-        InvalidPipelineId = 104,
-        NoAdapter = 105,
-        NoDevice = 106,
-        NoImgui = 107,
-        ImguiError = 108,
+        InvalidTargetId = 103,
+        InvalidBindGroupId = 104,
+        InvalidPipelineId = 105,
+        NoAdapter = 106,
+        NoDevice = 107,
+        NoImgui = 108,
+        ImguiError = 109,
+        InvalidShaderId = 110,
+        ShaderError = 111,
+        InvalidLutId = 112,
+        InvalidPaletteId = 113,
+        TooManyPalettes = 114,
+        InvalidDataLength = 115,
+        InvalidFontId = 116,
+        FontError = 117,
+        GlyphTooLarge = 118,
+        ImageError = 119,
+        IoError = 120,
+        InvalidRegionId = 121,
+        RegionOutOfBounds = 122,
+        SheetError = 123,
+        InvalidNineSlice = 124,
+        AtlasFull = 125,
+        RegionTextureMismatch = 126,
+        TextureDataSize = 127,
+        InvalidRowStride = 128,
+        UnsupportedImage = 129,
+        InvalidLoadId = 130,
+        FrameInProgress = 131,
+        TextureTooLarge = 132,
+        EmptyTexture = 133,
+        PartialMipmapUpdate = 134,
+        IndexedTextureFormat = 135,
         KelpAlreadyInitialised = 200,
         KelpNotInitialised = 201,
     }
@@ -327,7 +872,78 @@ namespace Kelp2d
     ///A pointer to an array of data someone else owns which may not be modified.
     [Serializable]
     [StructLayout(LayoutKind.Sequential)]
-    internal partial struct SliceInstanceData
+    internal partial struct SliceInstanceGPU
+    {
+        ///Pointer to start of immutable data.
+        IntPtr data;
+        ///Number of elements.
+        ulong len;
+    }
+
+    internal partial struct SliceInstanceGPU : IEnumerable<InstanceGPU>
+    {
+        public SliceInstanceGPU(GCHandle handle, ulong count)
+        {
+            this.data = handle.AddrOfPinnedObject();
+            this.len = count;
+        }
+        public SliceInstanceGPU(IntPtr handle, ulong count)
+        {
+            this.data = handle;
+            this.len = count;
+        }
+        #if (NETSTANDARD2_1_OR_GREATER || NET5_0_OR_GREATER || NETCOREAPP2_1_OR_GREATER)
+        public ReadOnlySpan<InstanceGPU> ReadOnlySpan
+        {
+            get
+            {
+                unsafe
+                {
+                    return new ReadOnlySpan<InstanceGPU>(this.data.ToPointer(), (int) this.len);
+                }
+            }
+        }
+        #endif
+        public InstanceGPU this[int i]
+        {
+            get
+            {
+                if (i >= Count) throw new IndexOutOfRangeException();
+                var size = Marshal.SizeOf(typeof(InstanceGPU));
+                var ptr = new IntPtr(data.ToInt64() + i * size);
+                return Marshal.PtrToStructure<InstanceGPU>(ptr);
+            }
+        }
+        public InstanceGPU[] Copied
+        {
+            get
+            {
+                var rval = new InstanceGPU[len];
+                for (var i = 0; i < (int) len; i++) {
+                    rval[i] = this[i];
+                }
+                return rval;
+            }
+        }
+        public int Count => (int) len;
+        public IEnumerator<InstanceGPU> GetEnumerator()
+        {
+            for (var i = 0; i < (int)len; ++i)
+            {
+                yield return this[i];
+            }
+        }
+        IEnumerator IEnumerable.GetEnumerator()
+        {
+            return this.GetEnumerator();
+        }
+    }
+
+
+    ///A pointer to an array of data someone else owns which may not be modified.
+    [Serializable]
+    [StructLayout(LayoutKind.Sequential)]
+    internal partial struct SlicePostEffectDesc
     {
         ///Pointer to start of immutable data.
         IntPtr data;
@@ -335,45 +951,45 @@ namespace Kelp2d
         ulong len;
     }
 
-    internal partial struct SliceInstanceData : IEnumerable<InstanceData>
+    internal partial struct SlicePostEffectDesc : IEnumerable<PostEffectDesc>
     {
-        public SliceInstanceData(GCHandle handle, ulong count)
+        public SlicePostEffectDesc(GCHandle handle, ulong count)
         {
             this.data = handle.AddrOfPinnedObject();
             this.len = count;
         }
-        public SliceInstanceData(IntPtr handle, ulong count)
+        public SlicePostEffectDesc(IntPtr handle, ulong count)
         {
             this.data = handle;
             this.len = count;
         }
         #if (NETSTANDARD2_1_OR_GREATER || NET5_0_OR_GREATER || NETCOREAPP2_1_OR_GREATER)
-        public ReadOnlySpan<InstanceData> ReadOnlySpan
+        public ReadOnlySpan<PostEffectDesc> ReadOnlySpan
         {
             get
             {
                 unsafe
                 {
-                    return new ReadOnlySpan<InstanceData>(this.data.ToPointer(), (int) this.len);
+                    return new ReadOnlySpan<PostEffectDesc>(this.data.ToPointer(), (int) this.len);
                 }
             }
         }
         #endif
-        public InstanceData this[int i]
+        public PostEffectDesc this[int i]
         {
             get
             {
                 if (i >= Count) throw new IndexOutOfRangeException();
-                var size = Marshal.SizeOf(typeof(InstanceData));
+                var size = Marshal.SizeOf(typeof(PostEffectDesc));
                 var ptr = new IntPtr(data.ToInt64() + i * size);
-                return Marshal.PtrToStructure<InstanceData>(ptr);
+                return Marshal.PtrToStructure<PostEffectDesc>(ptr);
             }
         }
-        public InstanceData[] Copied
+        public PostEffectDesc[] Copied
         {
             get
             {
-                var rval = new InstanceData[len];
+                var rval = new PostEffectDesc[len];
                 for (var i = 0; i < (int) len; i++) {
                     rval[i] = this[i];
                 }
@@ -381,7 +997,7 @@ namespace Kelp2d
             }
         }
         public int Count => (int) len;
-        public IEnumerator<InstanceData> GetEnumerator()
+        public IEnumerator<PostEffectDesc> GetEnumerator()
         {
             for (var i = 0; i < (int)len; ++i)
             {
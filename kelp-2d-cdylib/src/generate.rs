@@ -8,14 +8,37 @@ pub mod csharp {
 
     pub fn ffi_inventory() -> Inventory {
         InventoryBuilder::new()
+            .register(function!(compact_atlas))
             .register(function!(create_empty_texture))
+            .register(function!(create_indexed_texture))
+            .register(function!(create_lut))
+            .register(function!(create_palette))
+            .register(function!(create_post_shader))
+            .register(function!(create_region))
+            .register(function!(create_texture_dynamic))
+            .register(function!(create_texture_from_memory))
             .register(function!(create_texture_with_data))
+            .register(function!(create_texture_with_format))
             .register(function!(initialise))
+            .register(function!(load_progress))
+            .register(function!(load_texture_async_from_memory))
+            .register(function!(loaded_texture))
             .register(function!(present_frame))
+            .register(function!(remove_lut))
+            .register(function!(remove_texture))
             .register(function!(render_imgui))
             .register(function!(render_list))
+            .register(function!(set_atlas_padding))
+            .register(function!(set_atlas_mipmaps))
+            .register(function!(set_canvas))
+            .register(function!(set_post_effects))
             .register(function!(set_surface_size))
+            .register(function!(set_texture_color_space))
+            .register(function!(set_texture_deduplication))
+            .register(function!(transform_world))
             .register(function!(uninitialise))
+            .register(function!(update_texture_region))
+            .register(function!(update_texture_with_format))
             .inventory()
     }
 
@@ -4,22 +4,129 @@ mod generate;
 mod types;
 mod window_info;
 
-use interoptopus::{ffi_function, patterns::slice::FFISlice};
-use kelp_2d::{Camera, InstanceBatch, InstanceGPU, Kelp, KelpColor, KelpTextureId, RenderList};
+use interoptopus::{
+    ffi_function,
+    patterns::{slice::FFISlice, string::AsciiPointer},
+};
+use kelp_2d::{
+    AtlasCompaction, Camera, CanvasConfig, ColorSpace, InstanceBatch, InstanceGPU, Kelp, KelpColor, KelpLoadId,
+    KelpLutId, KelpPaletteId, KelpRect, KelpRegionId, KelpShaderId, KelpTextureId, LoadProgress, PostEffectDesc,
+    RenderList, Transform2D, UploadFormat,
+};
 use std::{ffi::c_void, mem::transmute, num::NonZeroU64, sync::OnceLock};
-use types::FFIError;
+use types::{FFIError, WorldMatrix};
 use window_info::WindowInfo;
 
 static mut KELP: OnceLock<Kelp> = OnceLock::new();
 
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn compact_atlas(out_report: &mut AtlasCompaction) -> FFIError {
+    match KELP.get_mut().map(Kelp::compact_atlas) {
+        Some(Ok(value)) => {
+            *out_report = value;
+            FFIError::Success
+        }
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
 #[ffi_function]
 #[no_mangle]
 pub unsafe extern "C" fn create_empty_texture(width: u32, height: u32, out_id: &mut KelpTextureId) -> FFIError {
-    match KELP.get_mut().map(|kelp| kelp.create_empty_texture(width, height)) {
-        Some(value) => {
+    match KELP.get_mut().map(|kelp| kelp.create_texture_empty(width, height)) {
+        Some(Ok(value)) => {
+            *out_id = value;
+            FFIError::Success
+        }
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn create_indexed_texture(
+    width: u32,
+    height: u32,
+    indices: FFISlice<u8>,
+    out_id: &mut KelpTextureId,
+) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.create_indexed_texture(width, height, indices.as_slice())) {
+        Some(Ok(value)) => {
+            *out_id = value;
+            FFIError::Success
+        }
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn create_lut(size: u32, data: FFISlice<u8>, out_id: &mut KelpLutId) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.create_lut(size, data.as_slice())) {
+        Some(Ok(value)) => {
+            *out_id = value;
+            FFIError::Success
+        }
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn create_palette(colors: FFISlice<u8>, out_id: &mut KelpPaletteId) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.create_palette(colors.as_slice())) {
+        Some(Ok(value)) => {
+            *out_id = value;
+            FFIError::Success
+        }
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn create_post_shader(source: AsciiPointer, out_id: &mut KelpShaderId) -> FFIError {
+    let Ok(source) = source.as_str() else {
+        return FFIError::Null;
+    };
+    match KELP.get_mut().map(|kelp| kelp.create_post_shader(source)) {
+        Some(Ok(value)) => {
+            *out_id = value;
+            FFIError::Success
+        }
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn create_region(texture: KelpTextureId, rect: KelpRect, out_id: &mut KelpRegionId) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.create_region(texture, rect)) {
+        Some(Ok(value)) => {
+            *out_id = value;
+            FFIError::Success
+        }
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn create_texture_from_memory(data: FFISlice<u8>, out_id: &mut KelpTextureId) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.create_texture_from_memory(data.as_slice())) {
+        Some(Ok(value)) => {
             *out_id = value;
             FFIError::Success
         }
+        Some(Err(err)) => err.into(),
         None => FFIError::KelpNotInitialised,
     }
 }
@@ -33,10 +140,47 @@ pub unsafe extern "C" fn create_texture_with_data(
     out_id: &mut KelpTextureId,
 ) -> FFIError {
     match KELP.get_mut().map(|kelp| kelp.create_texture_with_data(width, height, data.as_slice())) {
-        Some(value) => {
+        Some(Ok(value)) => {
             *out_id = value;
             FFIError::Success
         }
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn create_texture_dynamic(width: u32, height: u32, out_id: &mut KelpTextureId) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.create_texture_dynamic(width, height)) {
+        Some(Ok(value)) => {
+            *out_id = value;
+            FFIError::Success
+        }
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn create_texture_with_format(
+    width: u32,
+    height: u32,
+    data: FFISlice<u8>,
+    format: UploadFormat,
+    color_space: ColorSpace,
+    out_id: &mut KelpTextureId,
+) -> FFIError {
+    match KELP
+        .get_mut()
+        .map(|kelp| kelp.create_texture_with_format(width, height, data.as_slice(), format, color_space))
+    {
+        Some(Ok(value)) => {
+            *out_id = value;
+            FFIError::Success
+        }
+        Some(Err(err)) => err.into(),
         None => FFIError::KelpNotInitialised,
     }
 }
@@ -57,6 +201,51 @@ pub unsafe extern "C" fn initialise(window: WindowInfo, imgui_config: *const c_v
     }
 }
 
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn load_progress(out_progress: &mut LoadProgress) -> FFIError {
+    match KELP.get().map(Kelp::load_progress) {
+        Some(value) => {
+            *out_progress = value;
+            FFIError::Success
+        }
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn load_texture_async_from_memory(data: FFISlice<u8>, out_id: &mut KelpLoadId) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.load_texture_async_from_memory(data.to_vec())) {
+        Some(value) => {
+            *out_id = value;
+            FFIError::Success
+        }
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+/// Sets `out_ready` once the texture has loaded, after which `out_id` holds it
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn loaded_texture(
+    load: KelpLoadId,
+    out_id: &mut KelpTextureId,
+    out_ready: &mut bool,
+) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.loaded_texture(load)) {
+        Some(Ok(value)) => {
+            *out_ready = value.is_some();
+            if let Some(value) = value {
+                *out_id = value;
+            }
+            FFIError::Success
+        }
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
 #[ffi_function]
 #[no_mangle]
 pub unsafe extern "C" fn present_frame() -> FFIError {
@@ -67,6 +256,26 @@ pub unsafe extern "C" fn present_frame() -> FFIError {
     }
 }
 
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn remove_lut(lut: KelpLutId) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.remove_lut(lut)) {
+        Some(Ok(_)) => FFIError::Success,
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn remove_texture(texture: KelpTextureId) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.remove_texture(texture)) {
+        Some(Ok(_)) => FFIError::Success,
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
 #[ffi_function]
 #[no_mangle]
 pub unsafe extern "C" fn render_list(
@@ -81,7 +290,7 @@ pub unsafe extern "C" fn render_list(
             target: transmute(target),
             camera: (&camera).into(),
             clear: clear.map(Into::into),
-            instances: instances.iter().map(Into::into).collect(),
+            instances: instances.to_vec(),
             batches: batches.to_vec(),
         })
     }) {
@@ -101,6 +310,48 @@ pub unsafe extern "C" fn render_imgui(draw_data: *const c_void) -> FFIError {
     }
 }
 
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn set_atlas_padding(padding: u32, extrude: bool) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.set_atlas_padding(padding, extrude)) {
+        Some(_) => FFIError::Success,
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn set_atlas_mipmaps(mip_levels: u32) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.set_atlas_mipmaps(mip_levels)) {
+        Some(_) => FFIError::Success,
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn set_canvas(config: Option<&CanvasConfig>) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.set_canvas(config.copied())) {
+        Some(Ok(_)) => FFIError::Success,
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn set_post_effects(effects: FFISlice<PostEffectDesc>) -> FFIError {
+    let set = |kelp: &mut Kelp| {
+        let effects = effects.iter().map(|desc| kelp.post_effect_from_desc(desc)).collect::<Result<Vec<_>, _>>()?;
+        kelp.set_post_effects(&effects)
+    };
+    match KELP.get_mut().map(set) {
+        Some(Ok(_)) => FFIError::Success,
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
 #[ffi_function]
 #[no_mangle]
 pub unsafe extern "C" fn set_surface_size(width: u32, height: u32) -> FFIError {
@@ -110,6 +361,39 @@ pub unsafe extern "C" fn set_surface_size(width: u32, height: u32) -> FFIError {
     }
 }
 
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn set_texture_color_space(texture: KelpTextureId, color_space: ColorSpace) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.set_texture_color_space(texture, color_space)) {
+        Some(Ok(_)) => FFIError::Success,
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn set_texture_deduplication(deduplicate: bool) -> FFIError {
+    match KELP.get_mut().map(|kelp| kelp.set_texture_deduplication(deduplicate)) {
+        Some(_) => FFIError::Success,
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub extern "C" fn transform_world(transform: Transform2D, width: f32, height: f32) -> WorldMatrix {
+    let world = transform.world(width, height);
+    WorldMatrix {
+        col_1_x: world.x.x,
+        col_1_y: world.x.y,
+        col_2_x: world.y.x,
+        col_2_y: world.y.y,
+        trans_x: world.z.x,
+        trans_y: world.z.y,
+    }
+}
+
 #[ffi_function]
 #[no_mangle]
 pub unsafe extern "C" fn uninitialise() -> FFIError {
@@ -118,3 +402,41 @@ pub unsafe extern "C" fn uninitialise() -> FFIError {
         None => FFIError::KelpNotInitialised,
     }
 }
+
+/// Pass a `bytes_per_row` of 0 for tightly packed rows
+#[ffi_function]
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn update_texture_region(
+    texture: KelpTextureId,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    data: FFISlice<u8>,
+    bytes_per_row: u32,
+) -> FFIError {
+    let stride = (bytes_per_row != 0).then_some(bytes_per_row);
+    match KELP
+        .get()
+        .map(|kelp| kelp.update_texture_region(texture, x, y, width, height, data.as_slice(), stride))
+    {
+        Some(Ok(_)) => FFIError::Success,
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
+
+#[ffi_function]
+#[no_mangle]
+pub unsafe extern "C" fn update_texture_with_format(
+    texture: KelpTextureId,
+    data: FFISlice<u8>,
+    format: UploadFormat,
+) -> FFIError {
+    match KELP.get().map(|kelp| kelp.update_texture_with_format(texture, data.as_slice(), format)) {
+        Some(Ok(_)) => FFIError::Success,
+        Some(Err(err)) => err.into(),
+        None => FFIError::KelpNotInitialised,
+    }
+}
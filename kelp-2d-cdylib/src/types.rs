@@ -19,11 +19,50 @@ pub enum FFIError {
     NoDevice = 107,
     NoImgui = 108,
     ImguiError = 109,
+    InvalidShaderId = 110,
+    ShaderError = 111,
+    InvalidLutId = 112,
+    InvalidPaletteId = 113,
+    TooManyPalettes = 114,
+    InvalidDataLength = 115,
+    InvalidFontId = 116,
+    FontError = 117,
+    GlyphTooLarge = 118,
+    ImageError = 119,
+    IoError = 120,
+    InvalidRegionId = 121,
+    RegionOutOfBounds = 122,
+    SheetError = 123,
+    InvalidNineSlice = 124,
+    AtlasFull = 125,
+    RegionTextureMismatch = 126,
+    TextureDataSize = 127,
+    InvalidRowStride = 128,
+    UnsupportedImage = 129,
+    InvalidLoadId = 130,
+    FrameInProgress = 131,
+    TextureTooLarge = 132,
+    EmptyTexture = 133,
+    PartialMipmapUpdate = 134,
+    IndexedTextureFormat = 135,
     // Kelp FFI specific errors
     KelpAlreadyInitialised = 200,
     KelpNotInitialised = 201,
 }
 
+/// The world matrix of an instance, as its two columns and translation
+#[ffi_type]
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct WorldMatrix {
+    pub col_1_x: f32,
+    pub col_1_y: f32,
+    pub col_2_x: f32,
+    pub col_2_y: f32,
+    pub trans_x: f32,
+    pub trans_y: f32,
+}
+
 impl Default for FFIError {
     fn default() -> Self {
         Self::Success
@@ -49,6 +88,32 @@ impl From<KelpError> for FFIError {
             KelpError::NoDevice(_) => FFIError::NoDevice,
             KelpError::NoImgui => FFIError::NoImgui,
             KelpError::ImguiError(_) => FFIError::ImguiError,
+            KelpError::InvalidShaderId => FFIError::InvalidShaderId,
+            KelpError::ShaderError(_) => FFIError::ShaderError,
+            KelpError::InvalidLutId => FFIError::InvalidLutId,
+            KelpError::InvalidPaletteId => FFIError::InvalidPaletteId,
+            KelpError::TooManyPalettes => FFIError::TooManyPalettes,
+            KelpError::InvalidDataLength => FFIError::InvalidDataLength,
+            KelpError::InvalidFontId => FFIError::InvalidFontId,
+            KelpError::FontError(_) => FFIError::FontError,
+            KelpError::GlyphTooLarge => FFIError::GlyphTooLarge,
+            KelpError::ImageError(_) => FFIError::ImageError,
+            KelpError::IoError(_) => FFIError::IoError,
+            KelpError::InvalidRegionId => FFIError::InvalidRegionId,
+            KelpError::RegionOutOfBounds => FFIError::RegionOutOfBounds,
+            KelpError::SheetError(_) => FFIError::SheetError,
+            KelpError::InvalidNineSlice => FFIError::InvalidNineSlice,
+            KelpError::AtlasFull => FFIError::AtlasFull,
+            KelpError::RegionTextureMismatch => FFIError::RegionTextureMismatch,
+            KelpError::TextureDataSize { .. } => FFIError::TextureDataSize,
+            KelpError::InvalidRowStride => FFIError::InvalidRowStride,
+            KelpError::UnsupportedImage(_) => FFIError::UnsupportedImage,
+            KelpError::InvalidLoadId => FFIError::InvalidLoadId,
+            KelpError::FrameInProgress => FFIError::FrameInProgress,
+            KelpError::TextureTooLarge { .. } => FFIError::TextureTooLarge,
+            KelpError::EmptyTexture => FFIError::EmptyTexture,
+            KelpError::PartialMipmapUpdate => FFIError::PartialMipmapUpdate,
+            KelpError::IndexedTextureFormat => FFIError::IndexedTextureFormat,
         }
     }
 }
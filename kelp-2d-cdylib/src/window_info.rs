@@ -37,7 +37,7 @@ impl HasWindowHandle for WindowInfo {
                 win32_handle.hinstance = NonZeroIsize::new(self.second_handle as isize);
                 RawWindowHandle::Win32(win32_handle)
             }
-            WindowType::Xlib => RawWindowHandle::Xlib(XlibWindowHandle::new(self.window_handle as u32)),
+            WindowType::Xlib => RawWindowHandle::Xlib(XlibWindowHandle::new(self.window_handle as _)),
             WindowType::Wayland => RawWindowHandle::Wayland(WaylandWindowHandle::new(unsafe {
                 NonNull::new_unchecked(self.window_handle)
             })),
@@ -0,0 +1,91 @@
+use kelp_2d::{
+    BlendMode, Camera, CanvasConfig, CanvasScaling, InstanceData, InstanceMode, Kelp, KelpColor, RenderList,
+};
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::EventLoop,
+    window::Window,
+};
+
+const CANVAS_WIDTH: u32 = 320;
+const CANVAS_HEIGHT: u32 = 180;
+
+async fn run(event_loop: EventLoop<()>, window: Window) {
+    let size = window.inner_size();
+
+    let mut kelp = Kelp::new(&window, size.width, size.height, None).unwrap();
+    kelp.set_canvas(Some(CanvasConfig {
+        width: CANVAS_WIDTH,
+        height: CANVAS_HEIGHT,
+        scaling: CanvasScaling::Integer,
+        bars: KelpColor { r: 0.0, g: 0.0, b: 0.0, a: 1.0 },
+    }))
+    .unwrap();
+
+    // The camera covers the canvas, not the window
+    let camera = Camera::new(
+        CANVAS_WIDTH as f32 / 2.0,
+        CANVAS_HEIGHT as f32 / 2.0,
+        CANVAS_WIDTH as f32,
+        CANVAS_HEIGHT as f32,
+        0.0,
+        1.0,
+    );
+    let clear = Some(&KelpColor { r: 0.5, g: 0.0, b: 0.5, a: 1.0 });
+
+    // Create petal texture
+    let petal_texture = kelp.create_texture_from_path("./kelp-2d/examples/petal.png").unwrap();
+    let (tex_width, tex_height) = kelp.texture_size(petal_texture).unwrap();
+
+    let mut cursor = mint::Vector2 { x: 0.0, y: 0.0 };
+
+    event_loop
+        .run(move |event, event_loop_window_target| {
+            // Have the closure take ownership of kelp
+            let _ = kelp;
+
+            match event {
+                Event::WindowEvent { event: WindowEvent::Resized(size), .. } => {
+                    kelp.set_surface_size(size.width, size.height);
+                    // On macos the window needs to be redrawn manually after resizing
+                    window.request_redraw();
+                }
+                Event::WindowEvent { event: WindowEvent::CursorMoved { position, .. }, .. } => {
+                    cursor = kelp.window_to_canvas(position.x as f32, position.y as f32);
+                }
+                Event::WindowEvent { event: WindowEvent::RedrawRequested, .. } => {
+                    let instance = InstanceData {
+                        color: [1.0, 1.0, 1.0, 1.0].into(),
+                        mode: InstanceMode::Multiply,
+                        source_trans: [0.0, 0.0].into(),
+                        source_scale: [1.0, 1.0].into(),
+                        world: mint::RowMatrix3x2 {
+                            x: mint::Vector2 { x: tex_width as f32, y: 0.0 },
+                            y: mint::Vector2 { x: 0.0, y: tex_height as f32 },
+                            z: mint::Vector2 {
+                                x: cursor.x - tex_width as f32 / 2.0,
+                                y: cursor.y - tex_height as f32 / 2.0,
+                            },
+                        },
+                        ..Default::default()
+                    };
+                    let list = RenderList::new(None, &camera, clear)
+                        .add_instances(&kelp, petal_texture, false, BlendMode::ALPHA, &[instance])
+                        .unwrap();
+                    kelp.render_list(list).unwrap();
+                    kelp.present_frame().unwrap();
+                }
+                Event::AboutToWait => window.request_redraw(),
+                Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => event_loop_window_target.exit(),
+                _ => {}
+            }
+        })
+        .unwrap()
+}
+
+fn main() {
+    env_logger::init();
+    let event_loop = EventLoop::new().unwrap();
+    let window = Window::new(&event_loop).unwrap();
+    pollster::block_on(run(event_loop, window));
+}
@@ -1,21 +1,12 @@
-use kelp_2d::{BlendMode, Camera, InstanceData, InstanceMode, Kelp, KelpColor, RenderList};
+use kelp_2d::{BlendMode, Camera, InstanceData, InstanceMode, Kelp, KelpColor, RenderList, Transform2D};
 use rand::Rng;
-use std::{f32::consts::TAU, fs::File, path::Path};
+use std::f32::consts::TAU;
 use winit::{
     event::{Event, WindowEvent},
     event_loop::EventLoop,
     window::Window,
 };
 
-pub fn world_mat(render_x: f32, render_y: f32, rotation: f32, scale_x: f32, scale_y: f32) -> mint::RowMatrix3x2<f32> {
-    let (sin, cos) = rotation.sin_cos();
-    mint::RowMatrix3x2 {
-        x: mint::Vector2 { x: cos * scale_x, y: sin * scale_x },
-        y: mint::Vector2 { x: -sin * scale_y, y: cos * scale_y },
-        z: mint::Vector2 { x: render_x, y: render_y },
-    }
-}
-
 async fn run(event_loop: EventLoop<()>, window: Window) {
     let size = window.inner_size();
 
@@ -27,12 +18,8 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
     let clear = Some(&KelpColor { r: 0.5, g: 0.0, b: 0.5, a: 1.0 });
 
     // Create petal texture & bind group
-    let decoder = png::Decoder::new(File::open(Path::new("./kelp-2d/examples/petal.png")).unwrap());
-    let mut reader = decoder.read_info().unwrap();
-    let (tex_width, tex_height) = reader.info().size();
-    let mut data = vec![0; reader.output_buffer_size()];
-    reader.next_frame(&mut data).unwrap();
-    let petal_texture = kelp.create_texture_with_data(tex_width, tex_height, data.as_slice()).unwrap();
+    let petal_texture = kelp.create_texture_from_path("./kelp-2d/examples/petal.png").unwrap();
+    let (tex_width, tex_height) = kelp.texture_size(petal_texture).unwrap();
 
     // Set instance buffer
     let mut instance_data: Vec<InstanceData> = vec![];
@@ -42,15 +29,22 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
         let mode = InstanceMode::Multiply;
         let source_trans = [0.0, 0.0].into();
         let source_scale = [1.0, 1.0].into();
-        let world = world_mat(
-            rng.gen_range(0.0..(size.width as f32)),
-            rng.gen_range(0.0..(size.height as f32)),
-            rng.gen_range(0.0..(TAU)),
-            tex_width as f32,
-            tex_height as f32,
-        );
+        let world = Transform2D {
+            x: rng.gen_range(0.0..(size.width as f32)),
+            y: rng.gen_range(0.0..(size.height as f32)),
+            rotation: rng.gen_range(0.0..(TAU)),
+            ..Default::default()
+        }
+        .world(tex_width as f32, tex_height as f32);
 
-        instance_data.push(InstanceData { color, mode, source_trans, source_scale, world });
+        instance_data.push(InstanceData {
+            color,
+            mode,
+            source_trans,
+            source_scale,
+            world,
+            ..Default::default()
+        });
     }
     let mut instance_data_2: Vec<InstanceData> = vec![];
     for _ in 0..(1 << 14) {
@@ -59,15 +53,22 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
         let mode = InstanceMode::Multiply;
         let source_trans = [0.0, 0.0].into();
         let source_scale = [1.0, 1.0].into();
-        let world = world_mat(
-            rng.gen_range(0.0..(size.width as f32)),
-            rng.gen_range(0.0..(size.height as f32)),
-            rng.gen_range(0.0..(TAU)),
-            tex_width as f32,
-            tex_height as f32,
-        );
+        let world = Transform2D {
+            x: rng.gen_range(0.0..(size.width as f32)),
+            y: rng.gen_range(0.0..(size.height as f32)),
+            rotation: rng.gen_range(0.0..(TAU)),
+            ..Default::default()
+        }
+        .world(tex_width as f32, tex_height as f32);
 
-        instance_data_2.push(InstanceData { color, mode, source_trans, source_scale, world });
+        instance_data_2.push(InstanceData {
+            color,
+            mode,
+            source_trans,
+            source_scale,
+            world,
+            ..Default::default()
+        });
     }
 
     event_loop
@@ -1,5 +1,5 @@
 use kelp_2d::{BlendMode, Camera, InstanceData, InstanceMode, Kelp, KelpColor, RenderList};
-use std::{f32::consts::TAU, fs::File, path::Path};
+use std::f32::consts::TAU;
 use winit::{
     event::{Event, WindowEvent},
     event_loop::EventLoop,
@@ -17,12 +17,8 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
     let clear = Some(&KelpColor { r: 0.5, g: 0.0, b: 0.5, a: 1.0 });
 
     // Create petal texture & bind group
-    let decoder = png::Decoder::new(File::open(Path::new("./kelp-2d/examples/tester.png")).unwrap());
-    let mut reader = decoder.read_info().unwrap();
-    let (tex_width, tex_height) = reader.info().size();
-    let mut data = vec![0; reader.output_buffer_size()];
-    reader.next_frame(&mut data).unwrap();
-    let petal_texture = kelp.create_texture_with_data(tex_width, tex_height, data.as_slice()).unwrap();
+    let petal_texture = kelp.create_texture_from_path("./kelp-2d/examples/tester.png").unwrap();
+    let (tex_width, tex_height) = kelp.texture_size(petal_texture).unwrap();
 
     // Set instance buffer
     let mut instance_data: Vec<InstanceData> = vec![];
@@ -37,7 +33,14 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
             z: mint::Vector2 { x: (i * tex_width) as f32, y: 0.0 },
         };
 
-        instance_data.push(InstanceData { color, mode, source_trans, source_scale, world });
+        instance_data.push(InstanceData {
+            color,
+            mode,
+            source_trans,
+            source_scale,
+            world,
+            ..Default::default()
+        });
     }
     {
         let color = [1.0, 1.0, 1.0, 0.5].into();
@@ -49,7 +52,14 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
             y: mint::Vector2 { x: 0.0, y: tex_height as f32 },
             z: mint::Vector2 { x: 128.0, y: 128.0 },
         };
-        instance_data.push(InstanceData { color, mode, source_trans, source_scale, world });
+        instance_data.push(InstanceData {
+            color,
+            mode,
+            source_trans,
+            source_scale,
+            world,
+            ..Default::default()
+        });
     }
 
     let mut degs = 0.0;
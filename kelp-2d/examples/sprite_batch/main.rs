@@ -0,0 +1,113 @@
+use kelp_2d::{BlendMode, Camera, Kelp, KelpColor, KelpRect, SpriteBatch, SpriteFlip};
+use std::f32::consts::TAU;
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::EventLoop,
+    window::Window,
+};
+
+async fn run(event_loop: EventLoop<()>, window: Window) {
+    let size = window.inner_size();
+
+    let mut kelp = Kelp::new(&window, size.width, size.height, None).unwrap();
+
+    let mut camera =
+        Camera::new(size.width as f32 / 2.0, size.height as f32 / 2.0, size.width as f32, size.height as f32, 0.0, 1.0);
+    let clear = Some(&KelpColor { r: 0.5, g: 0.0, b: 0.5, a: 1.0 });
+
+    // Create tester and petal textures
+    let mut load_texture = |path: &str| {
+        let texture = kelp.create_texture_from_path(path).unwrap();
+        let (width, height) = kelp.texture_size(texture).unwrap();
+        (texture, width as f32, height as f32)
+    };
+    let (tester_texture, tester_width, tester_height) = load_texture("./kelp-2d/examples/tester.png");
+    let (petal_texture, petal_width, petal_height) = load_texture("./kelp-2d/examples/petal.png");
+
+    let mut degs = 0.0_f32;
+
+    event_loop
+        .run(move |event, event_loop_window_target| {
+            // Have the closure take ownership of kelp
+            let _ = kelp;
+
+            match event {
+                Event::WindowEvent { event: WindowEvent::Resized(size), .. } => {
+                    kelp.set_surface_size(size.width, size.height);
+                    camera = Camera::new(
+                        size.width as f32 / 2.0,
+                        size.height as f32 / 2.0,
+                        size.width as f32,
+                        size.height as f32,
+                        0.0,
+                        1.0,
+                    );
+                    // On macos the window needs to be redrawn manually after resizing
+                    window.request_redraw();
+                }
+                Event::WindowEvent { event: WindowEvent::RedrawRequested, .. } => {
+                    let white = [1.0, 1.0, 1.0, 1.0].into();
+                    let rotation = degs * TAU / 360.0;
+                    degs += 1.0;
+
+                    let mut batch = SpriteBatch::new(&kelp, None, &camera, clear);
+                    // Each quarter of the tester texture, flipped a different way, spinning around its centre
+                    let (quarter_width, quarter_height) = (tester_width / 2.0, tester_height / 2.0);
+                    for (i, flip) in [SpriteFlip::None, SpriteFlip::Horizontal, SpriteFlip::Vertical, SpriteFlip::Both]
+                        .into_iter()
+                        .enumerate()
+                    {
+                        let source = KelpRect {
+                            x: (i % 2) as f32 * quarter_width,
+                            y: (i / 2) as f32 * quarter_height,
+                            width: quarter_width,
+                            height: quarter_height,
+                        };
+                        batch
+                            .draw(
+                                tester_texture,
+                                [100.0 + i as f32 * 150.0, 100.0].into(),
+                                rotation,
+                                [2.0, 2.0].into(),
+                                [quarter_width / 2.0, quarter_height / 2.0].into(),
+                                white,
+                                flip,
+                                Some(source),
+                            )
+                            .unwrap();
+                    }
+                    // Additive petals start a new batch, then alpha blended ones another
+                    for (i, blend_mode) in
+                        [BlendMode::ADDITIVE, BlendMode::ADDITIVE, BlendMode::ALPHA].into_iter().enumerate()
+                    {
+                        batch.set_blend_mode(blend_mode);
+                        batch
+                            .draw(
+                                petal_texture,
+                                [150.0 + i as f32 * 100.0, 300.0].into(),
+                                -rotation,
+                                [1.0, 1.0].into(),
+                                [petal_width / 2.0, petal_height / 2.0].into(),
+                                [1.0, 0.5, 0.5, 1.0].into(),
+                                SpriteFlip::None,
+                                None,
+                            )
+                            .unwrap();
+                    }
+                    kelp.render_list(batch.finish()).unwrap();
+                    kelp.present_frame().unwrap();
+                }
+                Event::AboutToWait => window.request_redraw(),
+                Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => event_loop_window_target.exit(),
+                _ => {}
+            }
+        })
+        .unwrap()
+}
+
+fn main() {
+    env_logger::init();
+    let event_loop = EventLoop::new().unwrap();
+    let window = Window::new(&event_loop).unwrap();
+    pollster::block_on(run(event_loop, window));
+}
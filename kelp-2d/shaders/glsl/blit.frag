@@ -0,0 +1,13 @@
+#version 450
+
+layout(location = 0) in vec2 fsin_TextureUV;
+
+layout(location = 0) out vec4 fsout_Color;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler PointSampler;
+
+void main()
+{
+    fsout_Color = texture(sampler2D(Source, PointSampler), fsin_TextureUV);
+}
@@ -0,0 +1,49 @@
+#version 450
+
+layout(location = 0) in vec2 fsin_TextureUV;
+
+layout(location = 0) out vec4 fsout_Color;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler PointSampler;
+layout(set = 0, binding = 2) uniform sampler LinearSampler;
+layout(set = 0, binding = 3) uniform texture2D Aux;
+
+layout(push_constant) uniform PostBlock
+{
+    vec4 Params0;    // effect parameters
+    vec4 Params1;    // effect parameters
+    vec4 SourceSize; // xy contains source size in pixels, zw contains the size of one pixel in UVs
+};
+
+// Params0.x contains the stage: 0 extracts bright areas and blurs horizontally,
+//                               1 blurs vertically and adds the result over the original image in Aux
+// Params0.y contains the brightness threshold, Params0.z contains the radius in pixels, Params0.w the intensity
+void main()
+{
+    bool composite = Params0.x > 0.5;
+    vec2 step = composite ? vec2(0.0, SourceSize.w) : vec2(SourceSize.z, 0.0);
+    float radius = clamp(Params0.z, 0.0, 32.0);
+    float sigma = max(radius / 2.0, 0.001);
+    int taps = int(ceil(radius));
+
+    vec3 sum = vec3(0.0);
+    float total = 0.0;
+    for (int i = -taps; i <= taps; i++) {
+        float weight = exp(-float(i * i) / (2.0 * sigma * sigma));
+        vec3 color = texture(sampler2D(Source, LinearSampler), fsin_TextureUV + float(i) * step).rgb;
+        if (!composite) {
+            float brightness = max(color.r, max(color.g, color.b));
+            color *= max(brightness - Params0.y, 0.0) / max(brightness, 0.0001);
+        }
+        sum += weight * color;
+        total += weight;
+    }
+
+    if (composite) {
+        vec4 original = texture(sampler2D(Aux, PointSampler), fsin_TextureUV);
+        fsout_Color = vec4(original.rgb + Params0.w * sum / total, original.a);
+    } else {
+        fsout_Color = vec4(sum / total, 1.0);
+    }
+}
@@ -0,0 +1,36 @@
+#version 450
+
+layout(location = 0) in vec2 fsin_TextureUV;
+
+layout(location = 0) out vec4 fsout_Color;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler PointSampler;
+layout(set = 0, binding = 2) uniform sampler LinearSampler;
+layout(set = 0, binding = 3) uniform texture2D Aux;
+
+layout(push_constant) uniform PostBlock
+{
+    vec4 Params0;    // effect parameters
+    vec4 Params1;    // effect parameters
+    vec4 SourceSize; // xy contains source size in pixels, zw contains the size of one pixel in UVs
+};
+
+// Params0.xy contains the blur direction, Params0.z contains the radius in pixels
+void main()
+{
+    vec2 step = Params0.xy * SourceSize.zw;
+    float radius = clamp(Params0.z, 0.0, 32.0);
+    float sigma = max(radius / 2.0, 0.001);
+    int taps = int(ceil(radius));
+
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+    for (int i = -taps; i <= taps; i++) {
+        float weight = exp(-float(i * i) / (2.0 * sigma * sigma));
+        sum += weight * texture(sampler2D(Source, LinearSampler), fsin_TextureUV + float(i) * step);
+        total += weight;
+    }
+
+    fsout_Color = sum / total;
+}
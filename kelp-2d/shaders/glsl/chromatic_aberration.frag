@@ -0,0 +1,27 @@
+#version 450
+
+layout(location = 0) in vec2 fsin_TextureUV;
+
+layout(location = 0) out vec4 fsout_Color;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler PointSampler;
+layout(set = 0, binding = 2) uniform sampler LinearSampler;
+layout(set = 0, binding = 3) uniform texture2D Aux;
+
+layout(push_constant) uniform PostBlock
+{
+    vec4 Params0;    // effect parameters
+    vec4 Params1;    // effect parameters
+    vec4 SourceSize; // xy contains source size in pixels, zw contains the size of one pixel in UVs
+};
+
+// Params0.x contains the offset in pixels of the red and blue channels at the edges
+void main()
+{
+    vec2 offset = (fsin_TextureUV - 0.5) * 2.0 * Params0.x * SourceSize.zw;
+    vec4 color = texture(sampler2D(Source, LinearSampler), fsin_TextureUV);
+    color.r = texture(sampler2D(Source, LinearSampler), fsin_TextureUV + offset).r;
+    color.b = texture(sampler2D(Source, LinearSampler), fsin_TextureUV - offset).b;
+    fsout_Color = color;
+}
@@ -0,0 +1,28 @@
+#version 450
+
+layout(location = 0) in vec2 fsin_TextureUV;
+
+layout(location = 0) out vec4 fsout_Color;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler PointSampler;
+layout(set = 0, binding = 2) uniform sampler LinearSampler;
+layout(set = 0, binding = 3) uniform texture2D Aux;
+
+layout(push_constant) uniform PostBlock
+{
+    vec4 Params0;    // effect parameters
+    vec4 Params1;    // effect parameters
+    vec4 SourceSize; // xy contains source size in pixels, zw contains the size of one pixel in UVs
+};
+
+// Params0.x contains the brightness, Params0.y contains the contrast, Params0.z contains the saturation
+void main()
+{
+    vec4 color = texture(sampler2D(Source, PointSampler), fsin_TextureUV);
+    vec3 graded = color.rgb * Params0.x;
+    graded = (graded - 0.5) * Params0.y + 0.5;
+    float luma = dot(graded, vec3(0.2126, 0.7152, 0.0722));
+    graded = mix(vec3(luma), graded, Params0.z);
+    fsout_Color = vec4(max(graded, vec3(0.0)), color.a);
+}
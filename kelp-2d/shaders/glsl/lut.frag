@@ -0,0 +1,47 @@
+#version 450
+
+layout(location = 0) in vec2 fsin_TextureUV;
+
+layout(location = 0) out vec4 fsout_Color;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler PointSampler;
+layout(set = 0, binding = 2) uniform sampler LinearSampler;
+layout(set = 0, binding = 3) uniform texture2D Aux;
+
+layout(push_constant) uniform PostBlock
+{
+    vec4 Params0;    // effect parameters
+    vec4 Params1;    // effect parameters
+    vec4 SourceSize; // xy contains source size in pixels, zw contains the size of one pixel in UVs
+};
+
+// Params0.x contains the LUT size, Params0.y contains the intensity
+// Aux contains the LUT, as a strip of square blue slices laid out left to right
+
+vec3 linear_to_srgb(vec3 color)
+{
+    return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055, step(vec3(0.0031308), color));
+}
+
+vec3 srgb_to_linear(vec3 color)
+{
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(vec3(0.04045), color));
+}
+
+void main()
+{
+    vec4 color = texture(sampler2D(Source, PointSampler), fsin_TextureUV);
+    vec3 srgb = clamp(linear_to_srgb(color.rgb), 0.0, 1.0);
+    float size = Params0.x;
+
+    // Sample the two nearest blue slices, keeping red and green half a texel inside the slice
+    float blue = srgb.b * (size - 1.0);
+    float slice = floor(blue);
+    vec2 uv = (srgb.rg * (size - 1.0) + 0.5) / vec2(size * size, size);
+    vec3 lower = texture(sampler2D(Aux, LinearSampler), uv + vec2(slice / size, 0.0)).rgb;
+    vec3 upper = texture(sampler2D(Aux, LinearSampler), uv + vec2(min(slice + 1.0, size - 1.0) / size, 0.0)).rgb;
+    vec3 graded = srgb_to_linear(mix(lower, upper, blue - slice));
+
+    fsout_Color = vec4(mix(color.rgb, graded, Params0.y), color.a);
+}
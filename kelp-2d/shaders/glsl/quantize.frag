@@ -0,0 +1,82 @@
+#version 450
+
+layout(location = 0) in vec2 fsin_TextureUV;
+
+layout(location = 0) out vec4 fsout_Color;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler PointSampler;
+layout(set = 0, binding = 2) uniform sampler LinearSampler;
+layout(set = 0, binding = 3) uniform texture2D Aux;
+
+layout(push_constant) uniform PostBlock
+{
+    vec4 Params0;    // effect parameters
+    vec4 Params1;    // effect parameters
+    vec4 SourceSize; // xy contains source size in pixels, zw contains the size of one pixel in UVs
+};
+
+// Params0.x contains the palette row, Params0.y contains the number of colours in the palette,
+// Params0.z contains the dither mode (0 none, 1 ordered, 2 diffusion), Params0.w contains the dither strength
+// Aux contains the palette texture, whose colours are compared in sRGB space
+
+const float BAYER[16] = float[16](
+    0.0, 8.0, 2.0, 10.0,
+    12.0, 4.0, 14.0, 6.0,
+    3.0, 11.0, 1.0, 9.0,
+    15.0, 7.0, 13.0, 5.0
+);
+
+vec3 linear_to_srgb(vec3 color)
+{
+    return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055, step(vec3(0.0031308), color));
+}
+
+vec4 palette_color(int index)
+{
+    return texelFetch(sampler2D(Aux, PointSampler), ivec2(index, int(Params0.x)), 0);
+}
+
+// Returns the index of the nearest palette colour
+int nearest(vec3 srgb)
+{
+    int best = 0;
+    float best_distance = 1000.0;
+    for (int i = 0; i < int(Params0.y); i++) {
+        vec3 difference = srgb - linear_to_srgb(palette_color(i).rgb);
+        float distance = dot(difference, difference);
+        if (distance < best_distance) {
+            best = i;
+            best_distance = distance;
+        }
+    }
+    return best;
+}
+
+// Returns the sRGB error from quantising the source pixel at an offset, without any dithering
+vec3 quantize_error(ivec2 offset)
+{
+    vec2 uv = fsin_TextureUV + vec2(offset) * SourceSize.zw;
+    vec3 srgb = linear_to_srgb(texture(sampler2D(Source, PointSampler), uv).rgb);
+    return srgb - linear_to_srgb(palette_color(nearest(srgb)).rgb);
+}
+
+void main()
+{
+    vec4 color = texture(sampler2D(Source, PointSampler), fsin_TextureUV);
+    vec3 srgb = linear_to_srgb(color.rgb);
+    ivec2 pixel = ivec2(fsin_TextureUV * SourceSize.xy);
+
+    if (Params0.z == 1.0) {
+        // Ordered dithering nudges each pixel by a repeating threshold pattern
+        float threshold = BAYER[(pixel.y % 4) * 4 + pixel.x % 4] / 16.0 - 0.5;
+        srgb += Params0.w * threshold / 4.0;
+    } else if (Params0.z == 2.0) {
+        // Approximate error diffusion by taking on the undithered error of the previously visited neighbours
+        vec3 error = 7.0 * quantize_error(ivec2(-1, 0)) + 3.0 * quantize_error(ivec2(1, -1)) +
+                     5.0 * quantize_error(ivec2(0, -1)) + 1.0 * quantize_error(ivec2(-1, -1));
+        srgb += Params0.w * error / 16.0;
+    }
+
+    fsout_Color = vec4(palette_color(nearest(srgb)).rgb, color.a);
+}
@@ -0,0 +1,26 @@
+#version 450
+
+layout(location = 0) in vec2 fsin_TextureUV;
+
+layout(location = 0) out vec4 fsout_Color;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler PointSampler;
+layout(set = 0, binding = 2) uniform sampler LinearSampler;
+layout(set = 0, binding = 3) uniform texture2D Aux;
+
+layout(push_constant) uniform PostBlock
+{
+    vec4 Params0;    // effect parameters
+    vec4 Params1;    // effect parameters
+    vec4 SourceSize; // xy contains source size in pixels, zw contains the size of one pixel in UVs
+};
+
+// Params0.x contains the intensity, Params0.y contains the distance between lines in pixels
+void main()
+{
+    vec4 color = texture(sampler2D(Source, PointSampler), fsin_TextureUV);
+    float row = fsin_TextureUV.y * SourceSize.y;
+    float line = 0.5 - 0.5 * cos(6.28318530718 * row / max(Params0.y, 1.0));
+    fsout_Color = vec4(color.rgb * (1.0 - Params0.x * line), color.a);
+}
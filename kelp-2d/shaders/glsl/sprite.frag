@@ -1,25 +1,162 @@
 #version 450
 
 layout(location = 0) in vec2 fsin_TextureUV;
-layout(location = 1) flat in vec2 fsin_LayerSmooth;
+layout(location = 1) flat in vec4 fsin_Sampling;
 layout(location = 2) flat in vec4 fsin_Color;
 layout(location = 3) flat in vec4 fsin_Mode;
+layout(location = 4) flat in vec4 fsin_Effect;
+layout(location = 5) flat in vec4 fsin_EffectColor1;
+layout(location = 6) flat in vec4 fsin_EffectColor2;
+layout(location = 7) flat in vec4 fsin_Bounds;
+layout(location = 8) flat in vec2 fsin_TextureInfo;
 
 layout(location = 0) out vec4 fsout_Color;
 
 layout(set = 0, binding = 1) uniform texture2DArray Texture;
 layout(set = 0, binding = 2) uniform sampler PointSampler;
 layout(set = 0, binding = 3) uniform sampler LinearSampler;
+layout(set = 0, binding = 4) uniform texture2DArray IndexTexture; // non-sRGB view of the atlases
+layout(set = 0, binding = 5) uniform texture2D PaletteTexture;
+layout(set = 0, binding = 6) uniform sampler TrilinearSampler;
+layout(set = 0, binding = 7) uniform texture2DArray IndexAtlas; // single channel atlas of indexed textures
+
+// Mip level for trilinear sampling, set in main as derivatives are not available in non-uniform control flow
+float atlas_lod = 0.0;
+
+// Keeps UVs inside the texture or region bounds according to the wrap mode, so neighbouring allocations never show
+vec2 wrap_uv(vec2 uv)
+{
+    int mode = int(fsin_Sampling.w);
+    if (mode == 0) {
+        return uv;
+    }
+    vec2 size = fsin_Bounds.zw - fsin_Bounds.xy;
+    vec2 t = (uv - fsin_Bounds.xy) / size;
+    if (mode == 2) {
+        t = fract(t);
+    } else if (mode == 3) {
+        t = 1.0 - abs(mod(t, 2.0) - 1.0);
+    }
+    // Half a texel in from the edges, so that linear filtering does not reach past them either
+    vec2 half_texel = 0.5 / vec2(textureSize(sampler2DArray(Texture, PointSampler), 0).xy);
+    return clamp(fsin_Bounds.xy + t * size, fsin_Bounds.xy + half_texel, fsin_Bounds.zw - half_texel);
+}
+
+vec4 sample_atlas(vec2 uv)
+{
+    uv = wrap_uv(uv);
+    vec3 coords = vec3(uv, fsin_Sampling.x);
+    if (fsin_TextureInfo.y > 1.5) {
+        // Indexed textures store one palette index per texel, which must not be filtered
+        float index = texture(sampler2DArray(IndexAtlas, PointSampler), coords).r;
+        if (fsin_Sampling.z < 0) {
+            return vec4(vec3(index), 1.0);
+        }
+        return texelFetch(sampler2D(PaletteTexture, PointSampler), ivec2(int(round(index * 255.0)), int(fsin_Sampling.z)), 0);
+    } else if (fsin_Sampling.z >= 0) {
+        // Other textures drawn with a palette use their red channel as the index
+        float index = texture(sampler2DArray(IndexTexture, PointSampler), coords).r * 255.0;
+        return texelFetch(sampler2D(PaletteTexture, PointSampler), ivec2(int(round(index)), int(fsin_Sampling.z)), 0);
+    } else if (fsin_TextureInfo.y > 0.5) {
+        // Linear colour textures read the non-sRGB view, so their values are used as stored
+        if (fsin_Sampling.y > 1.5) {
+            return textureLod(sampler2DArray(IndexTexture, TrilinearSampler), coords, atlas_lod);
+        } else if (fsin_Sampling.y > 0) {
+            return texture(sampler2DArray(IndexTexture, LinearSampler), coords);
+        } else {
+            return texture(sampler2DArray(IndexTexture, PointSampler), coords);
+        }
+    } else if (fsin_Sampling.y > 1.5) {
+        return textureLod(sampler2DArray(Texture, TrilinearSampler), coords, atlas_lod);
+    } else if (fsin_Sampling.y > 0) {
+        return texture(sampler2DArray(Texture, LinearSampler), coords);
+    } else {
+        return texture(sampler2DArray(Texture, PointSampler), coords);
+    }
+}
+
+// Signed distance from the glyph edge in field units, positive inside
+float field_distance(vec2 uv, bool multi_channel)
+{
+    // Distances are linear data, so they are read through the non-sRGB view and always filtered
+    vec4 field = texture(sampler2DArray(IndexTexture, LinearSampler), vec3(wrap_uv(uv), fsin_Sampling.x));
+    float median = max(min(field.r, field.g), min(max(field.r, field.g), field.b));
+    return (multi_channel ? median : field.r) - 0.5;
+}
+
+// Composites non-premultiplied colours as if drawn bottom first
+vec4 blend_over(vec4 top, vec4 bottom)
+{
+    float alpha = top.a + bottom.a * (1.0 - top.a);
+    vec3 color = top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a);
+    return vec4(alpha > 0.0 ? color / alpha : vec3(0.0), alpha);
+}
+
+vec3 rgb_to_hsv(vec3 c)
+{
+    vec4 k = vec4(0.0, -1.0 / 3.0, 2.0 / 3.0, -1.0);
+    vec4 p = mix(vec4(c.bg, k.wz), vec4(c.gb, k.xy), step(c.b, c.g));
+    vec4 q = mix(vec4(p.xyw, c.r), vec4(c.r, p.yzx), step(p.x, c.r));
+    float d = q.x - min(q.w, q.y);
+    return vec3(abs(q.z + (q.w - q.y) / (6.0 * d + 1e-10)), d / (q.x + 1e-10), q.x);
+}
+
+vec3 hsv_to_rgb(vec3 c)
+{
+    vec3 p = abs(fract(c.xxx + vec3(1.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0);
+    return c.z * mix(vec3(1.0), clamp(p - 1.0, 0.0, 1.0), c.y);
+}
 
 void main()
 {
+    // Screen space texels per atlas texel, computed up front so derivatives stay in uniform control flow
+    vec2 atlas_size = vec2(textureSize(sampler2DArray(Texture, PointSampler), 0).xy);
+    vec2 screen_texels = 1.0 / fwidth(fsin_TextureUV * atlas_size);
+    atlas_lod = clamp(log2(max(1.0 / screen_texels.x, 1.0 / screen_texels.y)), 0.0, fsin_TextureInfo.x);
+
     // Sample the texture atlases
-    vec3 coords = vec3(fsin_TextureUV, fsin_LayerSmooth.x);
-    vec4 pixel;
-    if (fsin_LayerSmooth.y > 0) {
-        pixel = texture(sampler2DArray(Texture, LinearSampler), coords);
-    } else {
-        pixel = texture(sampler2DArray(Texture, PointSampler), coords);
+    vec4 pixel = sample_atlas(fsin_TextureUV);
+
+    // Apply built in effects to the sampled pixel
+    int effect = int(fsin_Mode.w);
+    float outline = 0.0;
+    if (effect == 1) {
+        // grayscale, Effect.x contains amount
+        float luma = dot(pixel.rgb, vec3(0.2126, 0.7152, 0.0722));
+        pixel.rgb = mix(pixel.rgb, vec3(luma), fsin_Effect.x);
+    } else if (effect == 2) {
+        // invert, Effect.x contains amount
+        pixel.rgb = mix(pixel.rgb, 1.0 - pixel.rgb, fsin_Effect.x);
+    } else if (effect == 4) {
+        // hue shift, Effect.x contains hue offset in radians, Effect.yz contain saturation and value multipliers
+        vec3 hsv = rgb_to_hsv(pixel.rgb);
+        hsv.x = fract(hsv.x + fsin_Effect.x / 6.28318530718);
+        hsv.yz *= fsin_Effect.yz;
+        pixel.rgb = hsv_to_rgb(clamp(hsv, 0.0, 1.0));
+    } else if (effect == 5 && pixel.a < 0.5) {
+        // outline, drawn on transparent pixels next to opaque ones in the atlas
+        vec2 texel = 1.0 / atlas_size;
+        float neighbours = max(
+            max(sample_atlas(fsin_TextureUV + vec2(texel.x, 0.0)).a, sample_atlas(fsin_TextureUV - vec2(texel.x, 0.0)).a),
+            max(sample_atlas(fsin_TextureUV + vec2(0.0, texel.y)).a, sample_atlas(fsin_TextureUV - vec2(0.0, texel.y)).a)
+        );
+        outline = step(0.5, neighbours);
+    }
+
+    if (effect == 6 || effect == 7) {
+        // distance field, Effect.x contains the distance range in texels the field was generated with,
+        // Effect.y contains outline width in field units, Effect.zw contain shadow offset in texels
+        bool multi_channel = effect == 7;
+        float screen_range = max(0.5 * fsin_Effect.x * (screen_texels.x + screen_texels.y), 1.0);
+        float edge_distance = field_distance(fsin_TextureUV, multi_channel);
+        float shadow_distance = field_distance(fsin_TextureUV - fsin_Effect.zw / atlas_size, multi_channel);
+        float fill = clamp(screen_range * edge_distance + 0.5, 0.0, 1.0);
+        float outline_fill = clamp(screen_range * (edge_distance + fsin_Effect.y) + 0.5, 0.0, 1.0);
+        float shadow_fill = clamp(screen_range * (shadow_distance + fsin_Effect.y) + 0.5, 0.0, 1.0);
+        vec4 shadow_color = vec4(fsin_EffectColor2.rgb, fsin_EffectColor2.a * shadow_fill);
+        vec4 outline_color = vec4(fsin_EffectColor1.rgb, fsin_EffectColor1.a * outline_fill);
+        fsout_Color = blend_over(vec4(fsin_Color.rgb, fsin_Color.a * fill), blend_over(outline_color, shadow_color));
+        return;
     }
 
     // Apply basic sprite modes (based on MVW shader by ChevyRay)
@@ -28,11 +165,11 @@ void main()
         fsin_Mode.y * fsin_Color * pixel.a + // wash
         fsin_Mode.z * fsin_Color;             // veto
 
-    // MSDF rendering idea - to be investigated later
-    // if (fsin_Mode.w != 0) {
-    //     float median = max(min(pixel.r, pixel.g), min(max(pixel.r, pixel.g), pixel.b))
-    //     float distance = fsin_Mode.w * (median - 0.5);
-    //     fsout_Color = fsin_Color;
-    //     fsout_Color.a *= clamp(distance + 0.5, 0.0, 1.0);
-    // }
+    if (effect == 3) {
+        // flash, Effect.rgb contains additive colour, Effect.a contains amount
+        fsout_Color.rgb = min(fsout_Color.rgb + fsin_Effect.rgb * fsin_Effect.a, 1.0);
+    } else if (effect == 5) {
+        // outline, Effect contains outline colour
+        fsout_Color = mix(fsout_Color, fsin_Effect, outline);
+    }
 }
@@ -3,20 +3,31 @@
 layout(location = 0) in vec2 Position;
 
 layout(location = 0) out vec2 fsin_TextureUV;
-layout(location = 1) flat out vec2 fsin_LayerSmooth;
+layout(location = 1) flat out vec4 fsin_Sampling;
 layout(location = 2) flat out vec4 fsin_Color;
 layout(location = 3) flat out vec4 fsin_Mode;
+layout(location = 4) flat out vec4 fsin_Effect;
+layout(location = 5) flat out vec4 fsin_EffectColor1;
+layout(location = 6) flat out vec4 fsin_EffectColor2;
+layout(location = 7) flat out vec4 fsin_Bounds;
+layout(location = 8) flat out vec2 fsin_TextureInfo;
 
 struct Instance 
 {
     vec4 Color;       // contains color to tint sprite
-    vec4 Mode;        // xyz contains draw mode options, w currently unused
-    vec2 LayerSmooth; // x contains texture array layer, y contains smooth filtering option
+    vec4 Mode;        // xyz contains draw mode options, w contains built in effect
+    vec4 Sampling;    // x contains texture array layer, y contains filtering (0 point, 1 linear, 2 trilinear),
+                      // z contains palette row for indexed textures or -1, w contains wrap mode
+    vec4 Effect;      // contains built in effect parameters
+    vec4 EffectColor1; // contains built in effect colour, such as distance field outline
+    vec4 EffectColor2; // contains built in effect colour, such as distance field shadow
+    vec4 Bounds;      // xy contains UV min, zw contains UV max of the texture or region, for wrapping
     vec2 SourceTrans; // contains UV translation
     vec2 SourceScale; // contains UV scale
     vec2 WorldCol1;   // world matrix 2x2 1st col
     vec2 WorldCol2;   // world matrix 2x2 2nd col
     vec2 WorldTrans;  // world matrix translation
+    vec2 TextureInfo; // x contains highest mip level, y contains 1 for linear colour textures and 2 for indexed textures
 };
 
 layout(push_constant) uniform CameraBlock
@@ -43,7 +54,12 @@ void main()
     gl_Position = ProjectionView * world * vec4(Position, 0, 1);
 
     fsin_TextureUV = Position * instance.SourceScale + instance.SourceTrans;
-    fsin_LayerSmooth = instance.LayerSmooth;
+    fsin_Sampling = instance.Sampling;
     fsin_Color = instance.Color;
     fsin_Mode = instance.Mode;
+    fsin_Effect = instance.Effect;
+    fsin_EffectColor1 = instance.EffectColor1;
+    fsin_EffectColor2 = instance.EffectColor2;
+    fsin_Bounds = instance.Bounds;
+    fsin_TextureInfo = instance.TextureInfo;
 }
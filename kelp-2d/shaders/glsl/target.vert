@@ -0,0 +1,13 @@
+#version 450
+
+layout(location = 0) out vec2 fsin_TextureUV;
+
+void main()
+{
+    // Generate a single triangle covering the whole target from the vertex index alone
+    vec2 uv = vec2(float((gl_VertexIndex << 1) & 2), float(gl_VertexIndex & 2));
+
+    gl_Position = vec4(uv.x * 2 - 1, 1 - uv.y * 2, 0, 1);
+
+    fsin_TextureUV = uv;
+}
@@ -0,0 +1,27 @@
+#version 450
+
+layout(location = 0) in vec2 fsin_TextureUV;
+
+layout(location = 0) out vec4 fsout_Color;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler PointSampler;
+layout(set = 0, binding = 2) uniform sampler LinearSampler;
+layout(set = 0, binding = 3) uniform texture2D Aux;
+
+layout(push_constant) uniform PostBlock
+{
+    vec4 Params0;    // effect parameters
+    vec4 Params1;    // effect parameters
+    vec4 SourceSize; // xy contains source size in pixels, zw contains the size of one pixel in UVs
+};
+
+// Params0.x contains the intensity, Params0.y contains the radius where darkening starts (0 centre to 1 corner),
+// Params0.z contains the softness of the falloff
+void main()
+{
+    vec4 color = texture(sampler2D(Source, PointSampler), fsin_TextureUV);
+    float distance = length(fsin_TextureUV - 0.5) * sqrt(2.0);
+    float vignette = smoothstep(Params0.y, Params0.y + max(Params0.z, 0.0001), distance);
+    fsout_Color = vec4(color.rgb * (1.0 - Params0.x * vignette), color.a);
+}
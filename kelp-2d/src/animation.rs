@@ -0,0 +1,263 @@
+use crate::{KelpError, KelpRegionId, SpriteFrame, SpriteSheet};
+use interoptopus::ffi_type;
+
+/// Frame duration used when a sheet does not give one, in seconds
+pub const DEFAULT_FRAME_DURATION: f32 = 0.1;
+
+/// What an animation does after its last frame
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum LoopMode {
+    /// Stop on the last frame
+    Once = 0,
+    /// Start again from the first frame
+    #[default]
+    Loop = 1,
+    /// Play backwards to the first frame, then forwards again, without repeating the end frames
+    PingPong = 2,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    pub frame: SpriteFrame,
+    /// In seconds
+    pub duration: f32,
+}
+
+/// A named sequence of sprite sheet frames
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    pub name: String,
+    pub frames: Vec<AnimationFrame>,
+    pub loop_mode: LoopMode,
+    /// How many times `Loop` plays through, or `PingPong` plays in either direction, before finishing on the frame
+    /// it reached, where 0 repeats forever
+    pub repeat: u32,
+}
+
+impl AnimationClip {
+    /// Create a clip from a range of a sheet's frames, using their own durations where the sheet has them
+    pub fn from_sheet(
+        sheet: &SpriteSheet,
+        name: &str,
+        frames: std::ops::Range<usize>,
+        default_duration: f32,
+        loop_mode: LoopMode,
+    ) -> Result<Self, KelpError> {
+        let Some(sheet_frames) = sheet.frames.get(frames.clone()) else {
+            let count = sheet.frames.len();
+            return Err(KelpError::SheetError(format!(
+                "frames {frames:?} are outside the {count} frames of the sheet"
+            )));
+        };
+        let frames = sheet_frames
+            .iter()
+            .map(|frame| AnimationFrame {
+                frame: frame.clone(),
+                duration: frame.duration.unwrap_or(default_duration),
+            })
+            .collect();
+        Ok(Self { name: name.to_string(), frames, loop_mode, repeat: 0 })
+    }
+
+    /// Total length of one play through, in seconds
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+/// Plays an animation clip, advanced by the time between frames
+#[derive(Debug, Clone)]
+pub struct Animator {
+    clip: AnimationClip,
+    index: usize,
+    time: f32,
+    forwards: bool,
+    finished: bool,
+    /// Play throughs completed, counted towards the clip's repeat
+    plays: u32,
+}
+
+impl Animator {
+    pub fn new(clip: AnimationClip) -> Self {
+        Self {
+            clip,
+            index: 0,
+            time: 0.0,
+            forwards: true,
+            finished: false,
+            plays: 0,
+        }
+    }
+
+    /// Switch to a different clip, starting from its first frame
+    pub fn set_clip(&mut self, clip: AnimationClip) {
+        *self = Self::new(clip);
+    }
+
+    /// Play the current clip again from its first frame
+    pub fn restart(&mut self) {
+        self.index = 0;
+        self.time = 0.0;
+        self.forwards = true;
+        self.finished = false;
+        self.plays = 0;
+    }
+
+    pub fn clip(&self) -> &AnimationClip {
+        &self.clip
+    }
+
+    /// Whether a clip that plays once, or a set number of times, has reached the end of its last frame
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The frame to draw now, or `None` if the clip is empty
+    pub fn frame(&self) -> Option<&SpriteFrame> {
+        self.clip.frames.get(self.index).map(|frame| &frame.frame)
+    }
+
+    /// The region to draw now, or `None` if the clip is empty
+    pub fn region(&self) -> Option<KelpRegionId> {
+        self.frame().map(|frame| frame.region)
+    }
+
+    /// Move the animation on by `delta` seconds, passing through as many frames as that covers
+    pub fn advance(&mut self, delta: f32) {
+        // A clip with no length would never leave its frame
+        if self.finished || self.clip.duration() <= 0.0 {
+            return;
+        }
+        self.time += delta;
+        while !self.finished && self.time >= self.clip.frames[self.index].duration {
+            self.time -= self.clip.frames[self.index].duration;
+            self.step();
+        }
+    }
+
+    /* private */
+    fn step(&mut self) {
+        let last = self.clip.frames.len() - 1;
+        match self.clip.loop_mode {
+            LoopMode::Once if self.index == last => self.finish(),
+            LoopMode::Once => self.index += 1,
+            LoopMode::Loop if self.index == last => {
+                if !self.repeat_finished() {
+                    self.index = 0;
+                }
+            }
+            LoopMode::Loop => self.index += 1,
+            LoopMode::PingPong if last == 0 => {}
+            LoopMode::PingPong => {
+                if (self.forwards && self.index == last) || (!self.forwards && self.index == 0) {
+                    if self.repeat_finished() {
+                        return;
+                    }
+                    self.forwards = !self.forwards;
+                }
+                self.index = if self.forwards { self.index + 1 } else { self.index - 1 };
+            }
+        }
+    }
+
+    /// Count a finished play through, finishing the animation if it was the last one
+    fn repeat_finished(&mut self) -> bool {
+        self.plays += 1;
+        let finished = self.clip.repeat > 0 && self.plays >= self.clip.repeat;
+        if finished {
+            self.finish();
+        }
+        finished
+    }
+
+    fn finish(&mut self) {
+        self.finished = true;
+        self.time = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(frames: usize, loop_mode: LoopMode, repeat: u32) -> AnimationClip {
+        let frames = (0..frames)
+            .map(|index| AnimationFrame {
+                frame: SpriteFrame {
+                    name: index.to_string(),
+                    region: KelpRegionId(index as u32),
+                    offset: [0.0, 0.0].into(),
+                    size: [8.0, 8.0].into(),
+                    source_size: [8.0, 8.0].into(),
+                    pivot: [0.0, 0.0].into(),
+                    duration: None,
+                },
+                duration: 1.0,
+            })
+            .collect();
+        AnimationClip { name: "test".to_string(), frames, loop_mode, repeat }
+    }
+
+    /// The frame shown after each of `steps` seconds
+    fn play(clip: AnimationClip, steps: usize) -> Vec<u32> {
+        let mut animator = Animator::new(clip);
+        (0..steps)
+            .map(|_| {
+                animator.advance(1.0);
+                animator.region().unwrap().0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plays_once_and_stops_on_the_last_frame() {
+        let mut animator = Animator::new(clip(3, LoopMode::Once, 0));
+        animator.advance(2.5);
+        assert_eq!((animator.region(), animator.finished()), (Some(KelpRegionId(2)), false));
+        animator.advance(10.0);
+        assert_eq!((animator.region(), animator.finished()), (Some(KelpRegionId(2)), true));
+    }
+
+    #[test]
+    fn loops_back_to_the_first_frame() {
+        assert_eq!(play(clip(3, LoopMode::Loop, 0), 7), [1, 2, 0, 1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn ping_pongs_without_repeating_the_end_frames() {
+        assert_eq!(play(clip(3, LoopMode::PingPong, 0), 8), [1, 2, 1, 0, 1, 2, 1, 0]);
+        assert_eq!(play(clip(1, LoopMode::PingPong, 0), 3), [0, 0, 0]);
+    }
+
+    #[test]
+    fn stops_after_the_repeat_count() {
+        assert_eq!(play(clip(2, LoopMode::Loop, 2), 6), [1, 0, 1, 1, 1, 1]);
+        // Each pass in either direction counts as one repeat
+        assert_eq!(play(clip(3, LoopMode::PingPong, 2), 6), [1, 2, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn passes_through_several_frames_in_one_advance() {
+        let mut animator = Animator::new(clip(4, LoopMode::Loop, 0));
+        animator.advance(6.5);
+        assert_eq!(animator.region(), Some(KelpRegionId(2)));
+    }
+
+    #[test]
+    fn restarts_from_the_first_frame() {
+        let mut animator = Animator::new(clip(2, LoopMode::Once, 0));
+        animator.advance(5.0);
+        assert!(animator.finished());
+        animator.restart();
+        assert_eq!((animator.region(), animator.finished()), (Some(KelpRegionId(0)), false));
+    }
+
+    #[test]
+    fn leaves_empty_clips_alone() {
+        let mut animator = Animator::new(clip(0, LoopMode::Loop, 0));
+        animator.advance(1.0);
+        assert_eq!(animator.region(), None);
+    }
+}
@@ -0,0 +1,366 @@
+use crate::{KelpError, KelpMap, KelpTextureId, LayoutFont};
+
+/// Pages a font may have, as many as the binary format's glyph page field can refer to
+const MAX_PAGES: f32 = 256.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BitmapGlyph {
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) x_offset: f32,
+    pub(crate) y_offset: f32,
+    pub(crate) x_advance: f32,
+    pub(crate) page: usize,
+}
+
+/// A pre-rendered font in the BMFont (AngelCode) format, drawn by scaling from the size it was made at
+#[derive(Debug, Default)]
+pub(crate) struct BitmapFont {
+    size: f32,
+    line_height: f32,
+    base: f32,
+    pub(crate) page_files: Vec<String>,
+    /// Each page in the atlas, with its size in pixels
+    pub(crate) pages: Vec<(KelpTextureId, u32, u32)>,
+    // Glyph indices used in layout are one more than the position in this map, leaving 0 as missing
+    glyphs: KelpMap<char, BitmapGlyph>,
+    kerning: KelpMap<(u16, u16), f32>,
+}
+
+impl BitmapFont {
+    /// Parse the contents of a `.fnt` file, in either the text or binary format
+    pub fn parse(data: &[u8]) -> Result<Self, KelpError> {
+        let mut font = if data.starts_with(b"BMF") {
+            Self::parse_binary(data)?
+        } else {
+            Self::parse_text(data)?
+        };
+        if font.line_height <= 0.0 {
+            return Err(KelpError::FontError("missing common line height".to_string()));
+        }
+        // The size may be negative, meaning it matches the character height rather than the cell height
+        font.size = if font.size == 0.0 {
+            font.line_height
+        } else {
+            font.size.abs()
+        };
+        if let Some(glyph) = font.glyphs.values().find(|glyph| glyph.page >= font.page_files.len()) {
+            return Err(KelpError::FontError(format!("glyph refers to missing page {}", glyph.page)));
+        }
+        Ok(font)
+    }
+
+    pub fn scale(&self, size: f32) -> f32 {
+        size / self.size
+    }
+
+    pub fn glyph(&self, glyph: u16) -> Option<&BitmapGlyph> {
+        self.glyphs.get_index((glyph as usize).checked_sub(1)?).map(|(_, glyph)| glyph)
+    }
+
+    /* private */
+    fn glyph_from_id(&self, id: u32) -> u16 {
+        char::from_u32(id)
+            .and_then(|character| self.glyphs.get_index_of(&character))
+            .map_or(0, |index| index as u16 + 1)
+    }
+
+    fn parse_text(data: &[u8]) -> Result<Self, KelpError> {
+        let text = std::str::from_utf8(data).map_err(|err| KelpError::FontError(err.to_string()))?;
+        let mut font = Self::default();
+        let mut kerning = Vec::new();
+        for line in text.lines() {
+            let (tag, attributes) = parse_line(line);
+            let number = |key: &str| -> Result<f32, KelpError> {
+                let value = attributes.iter().find(|(name, _)| *name == key).map_or("0", |(_, value)| value);
+                value.parse().map_err(|_| KelpError::FontError(format!("invalid {tag} {key}: {value}")))
+            };
+            match tag {
+                "info" => font.size = number("size")?,
+                "common" => {
+                    font.line_height = number("lineHeight")?;
+                    font.base = number("base")?;
+                }
+                "page" => {
+                    let id = number("id")?;
+                    if !(0.0..MAX_PAGES).contains(&id) {
+                        return Err(KelpError::FontError(format!("invalid page id: {id}")));
+                    }
+                    let id = id as usize;
+                    let file = attributes.iter().find(|(name, _)| *name == "file").map_or("", |(_, file)| file);
+                    if font.page_files.len() <= id {
+                        font.page_files.resize(id + 1, String::new());
+                    }
+                    font.page_files[id] = file.to_string();
+                }
+                "char" => font.insert_glyph(
+                    number("id")? as i64 as u32,
+                    BitmapGlyph {
+                        x: number("x")? as u32,
+                        y: number("y")? as u32,
+                        width: number("width")? as u32,
+                        height: number("height")? as u32,
+                        x_offset: number("xoffset")?,
+                        y_offset: number("yoffset")?,
+                        x_advance: number("xadvance")?,
+                        page: number("page")? as usize,
+                    },
+                ),
+                "kerning" => {
+                    kerning.push((number("first")? as i64 as u32, number("second")? as i64 as u32, number("amount")?))
+                }
+                _ => {}
+            }
+        }
+        font.insert_kerning(kerning);
+        Ok(font)
+    }
+
+    fn parse_binary(data: &[u8]) -> Result<Self, KelpError> {
+        if data.get(3) != Some(&3) {
+            return Err(KelpError::FontError("only version 3 of the binary format is supported".to_string()));
+        }
+        let mut font = Self::default();
+        let mut kerning = Vec::new();
+        let mut rest = &data[4..];
+        while !rest.is_empty() {
+            let truncated = || KelpError::FontError("truncated binary font".to_string());
+            let block_type = rest[0];
+            let size = u32::from_le_bytes(rest.get(1..5).ok_or_else(truncated)?.try_into().unwrap()) as usize;
+            let block = rest.get(5..5 + size).ok_or_else(truncated)?;
+            rest = &rest[5 + size..];
+            let u16_at = |offset: usize| u16::from_le_bytes([block[offset], block[offset + 1]]);
+            let i16_at = |offset: usize| i16::from_le_bytes([block[offset], block[offset + 1]]);
+            let u32_at = |offset: usize| u32::from_le_bytes(block[offset..offset + 4].try_into().unwrap());
+            match block_type {
+                1 if size >= 2 => font.size = i16_at(0) as f32,
+                2 if size >= 4 => {
+                    font.line_height = u16_at(0) as f32;
+                    font.base = u16_at(2) as f32;
+                }
+                3 => {
+                    font.page_files = block
+                        .split(|&byte| byte == 0)
+                        .filter(|name| !name.is_empty())
+                        .map(|name| String::from_utf8_lossy(name).into_owned())
+                        .collect()
+                }
+                4 => {
+                    for offset in (0..size - size % 20).step_by(20) {
+                        font.insert_glyph(
+                            u32_at(offset),
+                            BitmapGlyph {
+                                x: u16_at(offset + 4) as u32,
+                                y: u16_at(offset + 6) as u32,
+                                width: u16_at(offset + 8) as u32,
+                                height: u16_at(offset + 10) as u32,
+                                x_offset: i16_at(offset + 12) as f32,
+                                y_offset: i16_at(offset + 14) as f32,
+                                x_advance: i16_at(offset + 16) as f32,
+                                page: block[offset + 18] as usize,
+                            },
+                        );
+                    }
+                }
+                5 => {
+                    for offset in (0..size - size % 10).step_by(10) {
+                        kerning.push((u32_at(offset), u32_at(offset + 4), i16_at(offset + 8) as f32));
+                    }
+                }
+                _ => {}
+            }
+        }
+        font.insert_kerning(kerning);
+        Ok(font)
+    }
+
+    fn insert_glyph(&mut self, id: u32, glyph: BitmapGlyph) {
+        // An id of -1 is the glyph for missing characters, which layout looks up as '\0'
+        if let Some(character) = char::from_u32(id).or((id == u32::MAX).then_some('\0')) {
+            self.glyphs.insert(character, glyph);
+        }
+    }
+
+    // Kerning pairs may come before the glyphs they refer to, so they are resolved once all are known
+    fn insert_kerning(&mut self, kerning: Vec<(u32, u32, f32)>) {
+        for (first, second, amount) in kerning {
+            let pair = (self.glyph_from_id(first), self.glyph_from_id(second));
+            if pair.0 != 0 && pair.1 != 0 {
+                self.kerning.insert(pair, amount);
+            }
+        }
+    }
+}
+
+impl LayoutFont for BitmapFont {
+    fn glyph_index(&self, character: char) -> u16 {
+        match self.glyphs.get_index_of(&character).or_else(|| self.glyphs.get_index_of(&'\0')) {
+            Some(index) => index as u16 + 1,
+            None => 0,
+        }
+    }
+
+    fn advance(&self, glyph: u16, size: f32) -> f32 {
+        self.glyph(glyph).map_or(0.0, |glyph| glyph.x_advance * self.scale(size))
+    }
+
+    fn kern(&self, left: u16, right: u16, size: f32) -> f32 {
+        self.kerning.get(&(left, right)).map_or(0.0, |amount| amount * self.scale(size))
+    }
+
+    fn line_metrics(&self, size: f32) -> fontdue::LineMetrics {
+        let scale = self.scale(size);
+        fontdue::LineMetrics {
+            ascent: self.base * scale,
+            descent: (self.base - self.line_height) * scale,
+            line_gap: 0.0,
+            new_line_size: self.line_height * scale,
+        }
+    }
+}
+
+/// Split a line of the text format into its tag and `key=value` attributes, where values may be quoted
+fn parse_line(line: &str) -> (&str, Vec<(&str, &str)>) {
+    let line = line.trim();
+    let (tag, mut rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        let Some((key, value)) = rest.split_once('=') else {
+            break;
+        };
+        let (value, remaining) = match value.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => value.split_once(char::is_whitespace).unwrap_or((value, "")),
+        };
+        attributes.push((key.trim(), value));
+        rest = remaining;
+    }
+    (tag, attributes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT_FONT: &str = r#"info face="Test" size=-16 bold=0
+common lineHeight=20 base=15 scaleW=64 scaleH=64 pages=2
+page id=0 file="test_0.png"
+page id=1 file="test 1.png"
+chars count=3
+char id=65 x=1 y=2 width=8 height=10 xoffset=-1 yoffset=3 xadvance=9 page=0
+char id=86 x=10 y=2 width=9 height=10 xoffset=0 yoffset=3 xadvance=8 page=1
+char id=-1 x=20 y=2 width=6 height=10 xoffset=0 yoffset=3 xadvance=7 page=0
+kernings count=2
+kerning first=65 second=86 amount=-2
+kerning first=86 second=999 amount=-1
+"#;
+
+    fn block(block_type: u8, contents: &[u8]) -> Vec<u8> {
+        let mut block = vec![block_type];
+        block.extend((contents.len() as u32).to_le_bytes());
+        block.extend(contents);
+        block
+    }
+
+    fn binary_glyph(id: u32, x: u16, advance: i16, page: u8) -> Vec<u8> {
+        let mut glyph = id.to_le_bytes().to_vec();
+        for value in [x, 2, 8, 10] {
+            glyph.extend(value.to_le_bytes());
+        }
+        for value in [-1_i16, 3, advance] {
+            glyph.extend(value.to_le_bytes());
+        }
+        glyph.extend([page, 15]);
+        glyph
+    }
+
+    fn binary_font() -> Vec<u8> {
+        let mut info = 32_i16.to_le_bytes().to_vec();
+        info.extend([0; 12]);
+        let mut common = [40_u16, 30, 64, 64, 1].iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<_>>();
+        common.extend([0; 5]);
+        let mut glyphs = binary_glyph(65, 1, 18, 0);
+        glyphs.extend(binary_glyph(86, 10, 16, 0));
+        let mut kerning = 86_u32.to_le_bytes().to_vec();
+        kerning.extend(65_u32.to_le_bytes());
+        kerning.extend((-3_i16).to_le_bytes());
+        let mut font = b"BMF\x03".to_vec();
+        for (block_type, contents) in [(1, info), (2, common), (3, b"page.png\0".to_vec()), (4, glyphs), (5, kerning)] {
+            font.extend(block(block_type, &contents));
+        }
+        font
+    }
+
+    #[test]
+    fn parses_the_text_format() {
+        let font = BitmapFont::parse(TEXT_FONT.as_bytes()).unwrap();
+        assert_eq!(font.page_files, ["test_0.png", "test 1.png"]);
+        // A negative size is the character height, and layout scales from it
+        assert_eq!(font.scale(32.0), 2.0);
+        let v = font.glyph_index('V');
+        let glyph = font.glyph(v).unwrap();
+        assert_eq!((glyph.x, glyph.y, glyph.width, glyph.height, glyph.page), (10, 2, 9, 10, 1));
+        assert_eq!(font.advance(v, 32.0), 16.0);
+        let metrics = font.line_metrics(16.0);
+        assert_eq!((metrics.ascent, metrics.descent, metrics.new_line_size), (15.0, -5.0, 20.0));
+    }
+
+    #[test]
+    fn uses_the_missing_glyph_for_unknown_characters() {
+        let font = BitmapFont::parse(TEXT_FONT.as_bytes()).unwrap();
+        let missing = font.glyph_index('z');
+        assert_eq!(font.glyph(missing).unwrap().x, 20);
+    }
+
+    #[test]
+    fn kerns_known_pairs_in_the_text_format() {
+        let font = BitmapFont::parse(TEXT_FONT.as_bytes()).unwrap();
+        let (a, v) = (font.glyph_index('A'), font.glyph_index('V'));
+        assert_eq!(font.kern(a, v, 32.0), -4.0);
+        assert_eq!(font.kern(v, a, 32.0), 0.0);
+        // The pair with a glyph the font does not have is dropped
+        assert_eq!(font.kerning.len(), 1);
+    }
+
+    #[test]
+    fn parses_the_binary_format() {
+        let font = BitmapFont::parse(&binary_font()).unwrap();
+        assert_eq!(font.page_files, ["page.png"]);
+        assert_eq!(font.scale(16.0), 0.5);
+        let (a, v) = (font.glyph_index('A'), font.glyph_index('V'));
+        let glyph = font.glyph(a).unwrap();
+        assert_eq!((glyph.x, glyph.y, glyph.x_offset, glyph.y_offset), (1, 2, -1.0, 3.0));
+        assert_eq!(font.advance(v, 32.0), 16.0);
+        assert_eq!(font.kern(v, a, 16.0), -1.5);
+        let metrics = font.line_metrics(32.0);
+        assert_eq!((metrics.ascent, metrics.new_line_size), (30.0, 40.0));
+    }
+
+    #[test]
+    fn rejects_truncated_binary_fonts() {
+        let font = binary_font();
+        assert!(BitmapFont::parse(&font[..font.len() - 4]).is_err());
+        assert!(BitmapFont::parse(b"BMF\x02").is_err());
+    }
+
+    #[test]
+    fn rejects_fonts_without_a_line_height_or_with_missing_pages() {
+        assert!(BitmapFont::parse(b"info size=16\n").is_err());
+        let missing_page = TEXT_FONT.replace("page=1", "page=2");
+        assert!(BitmapFont::parse(missing_page.as_bytes()).is_err());
+        for id in ["256", "4000000000", "1e30", "-1", "NaN"] {
+            let bad_page = TEXT_FONT.replace("page id=1", &format!("page id={id}"));
+            assert!(matches!(BitmapFont::parse(bad_page.as_bytes()), Err(KelpError::FontError(_))));
+        }
+    }
+
+    #[test]
+    fn splits_quoted_attributes() {
+        let (tag, attributes) = parse_line(r#"page id=1 file="two words.png""#);
+        assert_eq!(tag, "page");
+        assert_eq!(attributes, [("id", "1"), ("file", "two words.png")]);
+    }
+}
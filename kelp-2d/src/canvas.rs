@@ -0,0 +1,87 @@
+use crate::{CanvasConfig, CanvasScaling};
+
+/// The rectangle of the window surface that the scene is presented into, in surface pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CanvasViewport {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+}
+
+impl CanvasViewport {
+    pub fn full(surface_width: u32, surface_height: u32) -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            width: surface_width as f32,
+            height: surface_height as f32,
+        }
+    }
+}
+
+impl CanvasConfig {
+    pub(crate) fn viewport(&self, surface_width: u32, surface_height: u32) -> CanvasViewport {
+        let (surface_width, surface_height) = (surface_width as f32, surface_height as f32);
+        let (canvas_width, canvas_height) = (self.width as f32, self.height as f32);
+        let fit_scale = (surface_width / canvas_width).min(surface_height / canvas_height);
+        let scale = match self.scaling {
+            // Fall back to fitting if the window is too small for even a 1x canvas
+            CanvasScaling::Integer if fit_scale >= 1.0 => fit_scale.floor(),
+            _ => fit_scale,
+        };
+        let width = (canvas_width * scale).min(surface_width);
+        let height = (canvas_height * scale).min(surface_height);
+        CanvasViewport {
+            x: ((surface_width - width) / 2.0).floor(),
+            y: ((surface_height - height) / 2.0).floor(),
+            width,
+            height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KelpColor;
+
+    fn canvas(scaling: CanvasScaling) -> CanvasConfig {
+        CanvasConfig {
+            width: 320,
+            height: 180,
+            scaling,
+            bars: KelpColor { r: 0.0, g: 0.0, b: 0.0, a: 1.0 },
+        }
+    }
+
+    fn viewport(x: f32, y: f32, width: f32, height: f32) -> CanvasViewport {
+        CanvasViewport { x, y, width, height }
+    }
+
+    #[test]
+    fn scales_by_whole_numbers_with_bars() {
+        let integer = canvas(CanvasScaling::Integer);
+        assert_eq!(integer.viewport(1280, 720), viewport(0.0, 0.0, 1280.0, 720.0));
+        assert_eq!(integer.viewport(1000, 700), viewport(20.0, 80.0, 960.0, 540.0));
+    }
+
+    #[test]
+    fn fits_keeping_the_aspect_ratio() {
+        let fit = canvas(CanvasScaling::Fit);
+        assert_eq!(fit.viewport(1000, 700), viewport(0.0, 68.0, 1000.0, 562.5));
+        assert_eq!(fit.viewport(640, 1000), viewport(0.0, 320.0, 640.0, 360.0));
+    }
+
+    #[test]
+    fn fits_windows_smaller_than_the_canvas() {
+        assert_eq!(canvas(CanvasScaling::Integer).viewport(160, 180), viewport(0.0, 45.0, 160.0, 90.0));
+    }
+
+    #[test]
+    fn has_no_area_in_an_empty_window() {
+        let empty = canvas(CanvasScaling::Integer).viewport(0, 0);
+        assert_eq!((empty.width, empty.height), (0.0, 0.0));
+        assert_eq!(CanvasViewport::full(0, 0), viewport(0.0, 0.0, 0.0, 0.0));
+    }
+}
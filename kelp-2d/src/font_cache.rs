@@ -0,0 +1,267 @@
+use crate::{
+    layout_text, BitmapFont, InstanceData, KelpError, KelpFontId, KelpMap, KelpTextureId, LayoutFont, PlacedGlyph,
+    TextInstances, TextLayout, TextSpan, TextureAllocation, TextureCache,
+};
+
+/// Size of each atlas allocation that glyphs are packed into
+const GLYPH_PAGE_SIZE: u32 = 1024;
+
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+struct GlyphKey {
+    font: KelpFontId,
+    size_bits: u32,
+    glyph: u16,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachedGlyph {
+    page: usize,
+    // Empty glyphs such as spaces take up no space in a page
+    allocation: Option<guillotiere::Allocation>,
+    metrics: fontdue::Metrics,
+    last_used: u64,
+}
+
+enum Font {
+    Vector(fontdue::Font),
+    Bitmap(BitmapFont),
+}
+
+struct GlyphPage {
+    texture: KelpTextureId,
+    allocator: guillotiere::AtlasAllocator,
+}
+
+/// Rasterises glyphs on demand into pages of the texture atlas, evicting those unused in the current frame when full
+#[derive(Default)]
+pub(crate) struct FontCache {
+    fonts: Vec<Font>,
+    pages: Vec<GlyphPage>,
+    glyphs: KelpMap<GlyphKey, CachedGlyph>,
+    frame: u64,
+}
+
+impl FontCache {
+    pub fn add_font(&mut self, data: &[u8]) -> Result<KelpFontId, KelpError> {
+        let font = fontdue::Font::from_bytes(data, fontdue::FontSettings::default())
+            .map_err(|err| KelpError::FontError(err.to_string()))?;
+        self.fonts.push(Font::Vector(font));
+        Ok(KelpFontId(self.fonts.len() as u32 - 1))
+    }
+
+    /// Add a bitmap font, once its pages have been loaded into the atlas
+    pub fn add_bitmap_font(&mut self, font: BitmapFont) -> KelpFontId {
+        self.fonts.push(Font::Bitmap(font));
+        KelpFontId(self.fonts.len() as u32 - 1)
+    }
+
+    /// Glyphs used before this are free to be evicted
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn layout_text(
+        &mut self,
+        texture_cache: &mut TextureCache,
+        queue: &wgpu::Queue,
+        texture_array: &wgpu::Texture,
+        font: KelpFontId,
+        spans: &[TextSpan],
+        position: mint::Vector2<f32>,
+        layout: &TextLayout,
+    ) -> Result<TextInstances, KelpError> {
+        let (glyphs, width, height) = match self.font(font)? {
+            Font::Vector(vector) => layout_text(vector, spans, layout),
+            Font::Bitmap(bitmap) => layout_text(bitmap, spans, layout),
+        };
+        let mut text_instances = TextInstances { width, height, ..Default::default() };
+        if let Font::Bitmap(bitmap) = self.font(font)? {
+            // Pixel fonts are kept crisp, rather than filtered
+            text_instances.smooth = false;
+            for placed in glyphs {
+                if let Some((texture, instance)) = bitmap_instance(bitmap, &placed, position) {
+                    text_instances.push(texture, instance);
+                }
+            }
+            return Ok(text_instances);
+        }
+        for placed in glyphs {
+            let key = GlyphKey { font, size_bits: placed.size.to_bits(), glyph: placed.glyph };
+            let cached = self.cache_glyph(texture_cache, queue, texture_array, key)?;
+            let Some(allocation) = cached.allocation else { continue };
+            let metrics = cached.metrics;
+            let page = self.pages[cached.page].texture;
+            let (glyph_width, glyph_height) = (metrics.width as f32, metrics.height as f32);
+            let x = (position.x + placed.x + metrics.xmin as f32).round();
+            let y = (position.y + placed.baseline - metrics.ymin as f32 - glyph_height).round();
+            text_instances.push(
+                page,
+                InstanceData {
+                    color: placed.color,
+                    source_trans: [allocation.rectangle.min.x as f32, allocation.rectangle.min.y as f32].into(),
+                    source_scale: [glyph_width / GLYPH_PAGE_SIZE as f32, glyph_height / GLYPH_PAGE_SIZE as f32].into(),
+                    world: mint::RowMatrix3x2 {
+                        x: mint::Vector2 { x: glyph_width, y: 0.0 },
+                        y: mint::Vector2 { x: 0.0, y: glyph_height },
+                        z: mint::Vector2 { x, y },
+                    },
+                    ..Default::default()
+                },
+            );
+        }
+        Ok(text_instances)
+    }
+
+    /* private */
+    fn font(&self, font: KelpFontId) -> Result<&Font, KelpError> {
+        self.fonts.get(font.0 as usize).ok_or(KelpError::InvalidFontId)
+    }
+
+    fn cache_glyph(
+        &mut self,
+        texture_cache: &mut TextureCache,
+        queue: &wgpu::Queue,
+        texture_array: &wgpu::Texture,
+        key: GlyphKey,
+    ) -> Result<CachedGlyph, KelpError> {
+        if !self.glyphs.contains_key(&key) {
+            let Font::Vector(font) = self.font(key.font)? else {
+                return Err(KelpError::InvalidFontId);
+            };
+            let (metrics, coverage) = font.rasterize_indexed(key.glyph, f32::from_bits(key.size_bits));
+            let (page, allocation) = if metrics.width == 0 || metrics.height == 0 {
+                (0, None)
+            } else {
+                let (page, allocation) = self.allocate_glyph(texture_cache, queue, texture_array, &metrics)?;
+                // Glyphs are white, with their coverage as alpha, so they can be tinted by the instance colour. The pad
+                // is written clear too, as it may hold part of a glyph evicted from the same space.
+                let (width, height) = (metrics.width, metrics.height);
+                let mut data = vec![0; (width + 1) * (height + 1) * 4];
+                for (row, coverage) in data.chunks_exact_mut((width + 1) * 4).zip(coverage.chunks_exact(width)) {
+                    for (pixel, &alpha) in row.chunks_exact_mut(4).zip(coverage) {
+                        pixel.copy_from_slice(&[255, 255, 255, alpha]);
+                    }
+                }
+                let origin = allocation.rectangle.min;
+                let page_rect = texture_cache.get_texture(self.pages[page].texture)?;
+                let size = (width as u32 + 1, height as u32 + 1);
+                write_page(queue, texture_array, &page_rect, (origin.x as u32, origin.y as u32), size, &data);
+                (page, Some(allocation))
+            };
+            self.glyphs.insert(key, CachedGlyph { page, allocation, metrics, last_used: self.frame });
+        }
+        let cached = self.glyphs.get_mut(&key).unwrap();
+        cached.last_used = self.frame;
+        Ok(*cached)
+    }
+
+    fn allocate_glyph(
+        &mut self,
+        texture_cache: &mut TextureCache,
+        queue: &wgpu::Queue,
+        texture_array: &wgpu::Texture,
+        metrics: &fontdue::Metrics,
+    ) -> Result<(usize, guillotiere::Allocation), KelpError> {
+        // Pad glyphs by a pixel, so that filtering does not bleed between neighbours
+        let size = guillotiere::Size::new(metrics.width as i32 + 1, metrics.height as i32 + 1);
+        if size.width > GLYPH_PAGE_SIZE as i32 || size.height > GLYPH_PAGE_SIZE as i32 {
+            return Err(KelpError::GlyphTooLarge);
+        }
+        if let Some(allocation) = self.try_allocate(size) {
+            return Ok(allocation);
+        }
+        self.evict_unused();
+        if let Some(allocation) = self.try_allocate(size) {
+            return Ok(allocation);
+        }
+        // Every page is full of glyphs in use, so start a new one, cleared to transparent
+        let texture = texture_cache.new_page_alloc(GLYPH_PAGE_SIZE, GLYPH_PAGE_SIZE).ok_or(KelpError::AtlasFull)?;
+        let page_rect = texture_cache.get_texture(texture)?;
+        let clear = vec![0; (GLYPH_PAGE_SIZE * GLYPH_PAGE_SIZE * 4) as usize];
+        write_page(queue, texture_array, &page_rect, (0, 0), (GLYPH_PAGE_SIZE, GLYPH_PAGE_SIZE), &clear);
+        let page_size = guillotiere::Size::new(GLYPH_PAGE_SIZE as i32, GLYPH_PAGE_SIZE as i32);
+        self.pages.push(GlyphPage {
+            texture,
+            allocator: guillotiere::AtlasAllocator::new(page_size),
+        });
+        self.try_allocate(size).ok_or(KelpError::GlyphTooLarge)
+    }
+
+    fn try_allocate(&mut self, size: guillotiere::Size) -> Option<(usize, guillotiere::Allocation)> {
+        self.pages.iter_mut().enumerate().find_map(|(index, page)| {
+            let mut allocation = page.allocator.allocate(size)?;
+            allocation.rectangle.max.x -= 1;
+            allocation.rectangle.max.y -= 1;
+            Some((index, allocation))
+        })
+    }
+
+    fn evict_unused(&mut self) {
+        let frame = self.frame;
+        let pages = &mut self.pages;
+        self.glyphs.retain(|_, glyph| {
+            let keep = glyph.last_used >= frame;
+            if let (false, Some(allocation)) = (keep, glyph.allocation) {
+                pages[glyph.page].allocator.deallocate(allocation.id);
+            }
+            keep
+        });
+    }
+}
+
+/// Bitmap glyphs are drawn straight from the font's own pages
+fn bitmap_instance(
+    font: &BitmapFont,
+    placed: &PlacedGlyph,
+    position: mint::Vector2<f32>,
+) -> Option<(KelpTextureId, InstanceData)> {
+    let glyph = font.glyph(placed.glyph).filter(|glyph| glyph.width > 0 && glyph.height > 0)?;
+    let &(texture, page_width, page_height) = font.pages.get(glyph.page)?;
+    let scale = font.scale(placed.size);
+    let top = placed.baseline - font.line_metrics(placed.size).ascent;
+    let (width, height) = (glyph.width as f32, glyph.height as f32);
+    let instance = InstanceData {
+        color: placed.color,
+        source_trans: [glyph.x as f32, glyph.y as f32].into(),
+        source_scale: [width / page_width as f32, height / page_height as f32].into(),
+        world: mint::RowMatrix3x2 {
+            x: mint::Vector2 { x: width * scale, y: 0.0 },
+            y: mint::Vector2 { x: 0.0, y: height * scale },
+            z: mint::Vector2 {
+                x: (position.x + placed.x + glyph.x_offset * scale).round(),
+                y: (position.y + top + glyph.y_offset * scale).round(),
+            },
+        },
+        ..Default::default()
+    };
+    Some((texture, instance))
+}
+
+/// Write pixels to a region of a glyph page, given relative to the page
+fn write_page(
+    queue: &wgpu::Queue,
+    texture_array: &wgpu::Texture,
+    page: &TextureAllocation,
+    (x, y): (u32, u32),
+    (width, height): (u32, u32),
+    data: &[u8],
+) {
+    let copy_texture = wgpu::ImageCopyTexture {
+        texture: texture_array,
+        mip_level: 0,
+        origin: wgpu::Origin3d {
+            x: page.rectangle.min.x as u32 + x,
+            y: page.rectangle.min.y as u32 + y,
+            z: page.id.layer,
+        },
+        aspect: wgpu::TextureAspect::All,
+    };
+    let write_size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+    let data_layout = wgpu::ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(4 * write_size.width),
+        rows_per_image: Some(write_size.height),
+    };
+    queue.write_texture(copy_texture, data, data_layout, write_size);
+}
@@ -0,0 +1,103 @@
+use crate::{ColorSpace, ImageFormat, KelpError, PixelFormat};
+
+/// An image decoded to 8 bit channels, in the pixel format closest to how it was stored
+pub(crate) struct DecodedImage {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) pixels: PixelFormat,
+    pub(crate) color_space: ColorSpace,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Decode an image file of any supported format, detected from its signature
+pub(crate) fn decode_image(data: &[u8]) -> Result<DecodedImage, KelpError> {
+    match ImageFormat::detect(data) {
+        #[cfg(feature = "image-png")]
+        Some(ImageFormat::Png) => decode_png(data),
+        #[cfg(feature = "image-qoi")]
+        Some(ImageFormat::Qoi) => decode_qoi(data),
+        #[cfg(feature = "image-jpeg")]
+        Some(ImageFormat::Jpeg) => decode_jpeg(data),
+        #[allow(unreachable_patterns)]
+        Some(format) => {
+            Err(KelpError::UnsupportedImage(format!("{format:?} images need the {} feature", feature(format))))
+        }
+        None => Err(KelpError::UnsupportedImage("not a PNG, QOI or JPEG file".to_string())),
+    }
+}
+
+/// Decode a PNG file, whatever its colour type and bit depth
+#[cfg(feature = "image-png")]
+pub(crate) fn decode_png(data: &[u8]) -> Result<DecodedImage, KelpError> {
+    let image_error = |err: png::DecodingError| KelpError::ImageError(err.to_string());
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(image_error)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(image_error)?;
+    buffer.truncate(info.buffer_size());
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => PixelFormat::Rgba8,
+        png::ColorType::Rgb => PixelFormat::Rgb8,
+        png::ColorType::GrayscaleAlpha => PixelFormat::GrayAlpha8,
+        png::ColorType::Grayscale => PixelFormat::Gray8,
+        png::ColorType::Indexed => unreachable!("indexed images are expanded by the decoder"),
+    };
+    Ok(DecodedImage {
+        width: info.width,
+        height: info.height,
+        pixels,
+        color_space: ColorSpace::Srgb,
+        data: buffer,
+    })
+}
+
+/// Decode a QOI file, which records whether its colours are sRGB or linear
+#[cfg(feature = "image-qoi")]
+fn decode_qoi(data: &[u8]) -> Result<DecodedImage, KelpError> {
+    let (header, data) = qoi::decode_to_vec(data).map_err(|err| KelpError::ImageError(err.to_string()))?;
+    let pixels = match header.channels {
+        qoi::Channels::Rgb => PixelFormat::Rgb8,
+        qoi::Channels::Rgba => PixelFormat::Rgba8,
+    };
+    let color_space = match header.colorspace {
+        qoi::ColorSpace::Srgb => ColorSpace::Srgb,
+        qoi::ColorSpace::Linear => ColorSpace::Linear,
+    };
+    Ok(DecodedImage {
+        width: header.width,
+        height: header.height,
+        pixels,
+        color_space,
+        data,
+    })
+}
+
+/// Decode a baseline or progressive JPEG file in greyscale or RGB
+#[cfg(feature = "image-jpeg")]
+fn decode_jpeg(data: &[u8]) -> Result<DecodedImage, KelpError> {
+    let mut decoder = jpeg_decoder::Decoder::new(data);
+    let data = decoder.decode().map_err(|err| KelpError::ImageError(err.to_string()))?;
+    let info = decoder.info().expect("image info is read before decoding");
+    let pixels = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => PixelFormat::Gray8,
+        jpeg_decoder::PixelFormat::RGB24 => PixelFormat::Rgb8,
+        jpeg_decoder::PixelFormat::L16 => {
+            return Err(KelpError::UnsupportedImage("16 bit greyscale JPEG images are not supported".to_string()))
+        }
+        jpeg_decoder::PixelFormat::CMYK32 => {
+            return Err(KelpError::UnsupportedImage("CMYK JPEG images are not supported".to_string()))
+        }
+    };
+    let (width, height) = (info.width as u32, info.height as u32);
+    Ok(DecodedImage { width, height, pixels, color_space: ColorSpace::Srgb, data })
+}
+
+/// The feature that enables loading each image format
+fn feature(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "image-png",
+        ImageFormat::Qoi => "image-qoi",
+        ImageFormat::Jpeg => "image-jpeg",
+    }
+}
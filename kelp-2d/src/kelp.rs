@@ -1,5 +1,10 @@
 use crate::{
-    ImGuiConfig, InstanceGPU, KelpError, KelpTargetId, KelpTextureId, PipelineCache, RenderList, TextureCache,
+    decode_image, downsample, pack_rows, pad_pixels, row_stride, AtlasCompaction, BitmapFont, CanvasConfig,
+    CanvasViewport, ColorSpace, DecodedImage, FontCache, ImGuiConfig, InstanceGPU, KelpError, KelpFontId, KelpLoadId,
+    KelpLutId, KelpPaletteId, KelpRect, KelpRegionId, KelpShaderId, KelpTargetId, KelpTextureId, LoadProgress,
+    LoadSource, PaletteCache, PipelineCache, PixelFormat, PostEffect, PostEffectDesc, PostProcess, RenderList,
+    SheetGrid, SpriteSheet, TextInstances, TextLayout, TextSpan, TextureCache, TextureLoader, UploadFormat,
+    ATLAS_MIP_LEVELS,
 };
 use bytemuck::NoUninit;
 use kelp_2d_imgui_wgpu::{DrawData, ImGuiRenderer, RendererConfig};
@@ -9,6 +14,7 @@ use std::{
     cell::{OnceCell, RefCell},
     mem::size_of,
     num::NonZeroU64,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 use wgpu::util::DeviceExt;
@@ -31,10 +37,19 @@ pub struct Kelp {
     pub(crate) instance_buffer: wgpu::Buffer,
     pub(crate) instance_staging_buffer: wgpu::Buffer,
     pub(crate) main_bind_group: wgpu::BindGroup,
+    /// Point, linear and trilinear samplers, kept for the bind groups of dedicated textures
+    pub(crate) samplers: [wgpu::Sampler; 3],
     pub(crate) texture_array: Rc<wgpu::Texture>,
+    /// Single channel atlas for indexed textures, a quarter of the size of the RGBA atlas per texel
+    pub(crate) index_atlas: wgpu::Texture,
     pub(crate) texture_cache: RefCell<TextureCache>,
     pub(crate) pipeline_cache: PipelineCache,
     pub(crate) imgui_renderer: Option<ImGuiRenderer>,
+    pub(crate) canvas: Option<CanvasConfig>,
+    pub(crate) post_process: PostProcess,
+    pub(crate) palette_cache: PaletteCache,
+    pub(crate) font_cache: FontCache,
+    pub(crate) texture_loader: TextureLoader,
     pub(crate) per_frame: OnceCell<PerFrame>,
 }
 
@@ -110,7 +125,7 @@ impl Kelp {
             ty: wgpu::BindingType::Buffer {
                 ty: wgpu::BufferBindingType::Storage { read_only: true },
                 has_dynamic_offset: false,
-                min_binding_size: NonZeroU64::new(16 + 16 + 16 + 64),
+                min_binding_size: NonZeroU64::new(size_of::<InstanceGPU>() as u64),
             },
             count: None,
         };
@@ -140,6 +155,23 @@ impl Kelp {
             count: None,
         };
 
+        let index_texture_bind_entry = wgpu::BindGroupLayoutEntry { binding: 4, ..texture_array_bind_entry };
+
+        let palette_texture_bind_entry = wgpu::BindGroupLayoutEntry {
+            binding: 5,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let trilinear_sampler_bind_entry = wgpu::BindGroupLayoutEntry { binding: 6, ..linear_sampler_bind_entry };
+
+        let index_atlas_bind_entry = wgpu::BindGroupLayoutEntry { binding: 7, ..texture_array_bind_entry };
+
         let sprite_bind_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Vertex Bind Group Layout"),
             entries: &[
@@ -147,6 +179,10 @@ impl Kelp {
                 texture_array_bind_entry,
                 point_sampler_bind_entry,
                 linear_sampler_bind_entry,
+                index_texture_bind_entry,
+                palette_texture_bind_entry,
+                trilinear_sampler_bind_entry,
+                index_atlas_bind_entry,
             ],
         });
 
@@ -173,64 +209,69 @@ impl Kelp {
         });
 
         // Create point sampler
-        let point_sampler =
-            device.create_sampler(&wgpu::SamplerDescriptor { label: Some("Point Sampler"), ..Default::default() });
+        // Point and linear samplers stay on the full size level, as only mipmapped textures fill the others
+        let point_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Point Sampler"),
+            lod_max_clamp: 0.0,
+            ..Default::default()
+        });
 
         // Create linear sampler
         let linear_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Linear Sampler"),
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Linear,
+            lod_max_clamp: 0.0,
             ..Default::default()
         });
 
-        // Create texture array - initially only 1 layer
-        let texture_array = Rc::new(device.create_texture(&wgpu::TextureDescriptor {
-            label: None,
-            size: wgpu::Extent3d { width: 2048, height: 2048, depth_or_array_layers: 1 },
+        // Create trilinear sampler, for mipmapped textures
+        let trilinear_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Trilinear Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        // Create texture array - initially only 1 layer, and without mip levels until they are used
+        let atlas_size = wgpu::Extent3d { width: 2048, height: 2048, depth_or_array_layers: 1 };
+        let texture_array = Rc::new(create_atlas_texture(&device, atlas_size, 1));
+        let index_atlas = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Index Atlas"),
+            size: atlas_size,
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format: wgpu::TextureFormat::R8Unorm,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
-        }));
+        });
+
+        // Create palette texture
+        let palette_cache = PaletteCache::new(&device);
 
         // Create sprite bind group
-        // TODO: fill this in
-        let sprite_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Sprite Bind Group"),
-            layout: &sprite_bind_layout,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: instance_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&texture_array.create_view(
-                        &wgpu::TextureViewDescriptor {
-                            dimension: Some(wgpu::TextureViewDimension::D2Array),
-                            ..Default::default()
-                        },
-                    )),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&point_sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::Sampler(&linear_sampler),
-                },
-            ],
-        });
+        let samplers = [point_sampler, linear_sampler, trilinear_sampler];
+        let sprite_bind_group = create_sprite_bind_group(
+            &device,
+            &sprite_bind_layout,
+            &instance_buffer,
+            &texture_array,
+            &samplers,
+            &palette_cache.create_view(),
+            &index_atlas,
+        );
 
         // Create caches
-        let texture_cache = RefCell::new(TextureCache::new(texture_array.as_ref(), point_sampler, linear_sampler));
+        let texture_cache = RefCell::new(TextureCache::new(texture_array.as_ref()));
         let pipeline_cache = PipelineCache::new(
             default_vertex_shader,
             default_fragment_shader,
             sprite_bind_layout,
             window_surface_config.format,
         );
+        let post_process = PostProcess::new(&device, window_surface_config.format);
 
         // Create ImGui renderer if passed a config, otherwise do not
         let imgui_renderer = imgui_config.map(|config| {
@@ -254,16 +295,31 @@ impl Kelp {
             instance_buffer,
             instance_staging_buffer,
             main_bind_group: sprite_bind_group,
+            samplers,
             texture_array,
+            index_atlas,
             texture_cache,
             pipeline_cache,
             imgui_renderer,
+            canvas: None,
+            post_process,
+            palette_cache,
+            font_cache: FontCache::default(),
+            texture_loader: TextureLoader::default(),
             per_frame: OnceCell::new(),
         })
     }
 
     pub fn present_frame(&mut self) -> Result<(), KelpError> {
-        if let Some(PerFrame { surface, mut buffer_encoder, draw_encoder, imgui_encoder, .. }) = self.per_frame.take() {
+        // The scene must be presented onto the surface every frame, even if nothing was drawn to it
+        if self.post_process.scene().is_some() {
+            self.start_frame()?;
+        }
+
+        if let Some(PerFrame {
+            surface, mut buffer_encoder, mut draw_encoder, imgui_encoder, ..
+        }) = self.per_frame.take()
+        {
             // Copy to the shader's instance buffer
             buffer_encoder.copy_buffer_to_buffer(
                 &self.instance_staging_buffer,
@@ -272,6 +328,20 @@ impl Kelp {
                 0,
                 self.instance_buffer.size(),
             );
+            // Apply post effects and scale the scene onto the surface, before any imgui rendering
+            let (width, height) = (surface.texture.width(), surface.texture.height());
+            let (viewport, bars) = match &self.canvas {
+                Some(canvas) => (canvas.viewport(width, height), (&canvas.bars).into()),
+                None => (CanvasViewport::full(width, height), wgpu::Color::BLACK),
+            };
+            self.post_process.present(
+                &self.device,
+                &mut draw_encoder,
+                &surface.texture,
+                viewport,
+                bars,
+                &self.palette_cache,
+            )?;
             // Submit and present the frame!
             let mut commands = vec![buffer_encoder.finish(), draw_encoder.finish()];
             if let Some(encoder) = imgui_encoder {
@@ -282,6 +352,7 @@ impl Kelp {
         } else {
             self.window_surface.get_current_texture()?.present()
         }
+        self.font_cache.end_frame();
         Ok(())
     }
 
@@ -293,7 +364,7 @@ impl Kelp {
         // TODO: Error if too many instances also
 
         // Initialise per frame resources if this is the first pass this frame
-        _ = self.per_frame.get_or_try_init(|| self.init_per_frame())?;
+        self.start_frame()?;
         let frame = self.per_frame.get_mut().unwrap();
 
         let camera_bytes = bytemuck::bytes_of(&render_list.camera);
@@ -313,7 +384,7 @@ impl Kelp {
         let tex_cache = self.texture_cache.borrow();
         let target_tex = match render_list.target {
             Some(target_id) => tex_cache.get_target(target_id)?,
-            None => &frame.surface.texture,
+            None => self.post_process.scene().unwrap_or(&frame.surface.texture),
         };
         let target_view = target_tex.create_view(&Default::default());
         let load = render_list.clear.map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear);
@@ -333,15 +404,25 @@ impl Kelp {
 
         // TODO: we don't really need the concept of batches in here anymore!
         let mut pipeline_index = usize::MAX; // starts invalid
+        let mut bound_texture = None; // a dedicated texture bound in place of the atlas
         for batch in &render_list.batches {
             let next_index = self.pipeline_cache.get_pipeline_index(None, batch.blend_mode)?;
+            let next_texture = batch.texture.is_dedicated().then_some(batch.texture);
 
-            if pipeline_index != next_index {
+            let pipeline_changed = pipeline_index != next_index;
+            if pipeline_changed {
                 pipeline_index = next_index;
                 wgpu_pass.set_pipeline(self.pipeline_cache.get_pipeline(pipeline_index)?);
                 wgpu_pass.set_push_constants(wgpu::ShaderStages::VERTEX, 0, camera_bytes);
                 wgpu_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-                wgpu_pass.set_bind_group(0, &self.main_bind_group, &[]);
+            }
+            if pipeline_changed || bound_texture != next_texture {
+                bound_texture = next_texture;
+                let bind_group = match next_texture {
+                    Some(texture) => &tex_cache.get_dedicated(texture)?.bind_group,
+                    None => &self.main_bind_group,
+                };
+                wgpu_pass.set_bind_group(0, bind_group, &[]);
             }
 
             let instance_range_end = frame.instance_offset + batch.instance_count;
@@ -352,8 +433,17 @@ impl Kelp {
         Ok(())
     }
 
-    pub fn create_texture_empty(&mut self, width: u32, height: u32) -> KelpTextureId {
-        self.texture_cache.borrow_mut().new_texture_alloc(width, height)
+    /// Create a texture in the atlas, or a texture of its own if it is larger than an atlas layer. Textures larger
+    /// than the device supports return `TextureTooLarge`, and those that would fit an empty layer return `AtlasFull`
+    /// when there is no room left for them.
+    pub fn create_texture_empty(&mut self, width: u32, height: u32) -> Result<KelpTextureId, KelpError> {
+        self.check_texture_size(width, height)?;
+        let texture_cache = self.texture_cache.get_mut();
+        if texture_cache.fits_layer(width, height) {
+            texture_cache.new_texture_alloc(width, height).ok_or(KelpError::AtlasFull)
+        } else {
+            Ok(self.create_dedicated_texture(width, height))
+        }
     }
 
     pub fn create_render_target(&mut self, width: u32, height: u32) -> KelpTargetId {
@@ -377,16 +467,67 @@ impl Kelp {
         height: u32,
         data: &[u8],
     ) -> Result<KelpTextureId, KelpError> {
-        let id = self.texture_cache.borrow_mut().new_texture_alloc(width, height);
-        self.update_texture(id, data)?;
-        Ok(id)
+        self.create_texture_with_format(width, height, data, UploadFormat::default(), ColorSpace::Srgb)
+    }
+
+    /// Create a handle to a sub-rectangle of a texture in pixels, which must lie within it
+    pub fn create_region(&mut self, texture: KelpTextureId, rect: KelpRect) -> Result<KelpRegionId, KelpError> {
+        self.texture_cache.get_mut().insert_region(texture, rect)
+    }
+
+    /// The texture and rectangle in pixels of a region
+    pub fn region(&self, region: KelpRegionId) -> Result<(KelpTextureId, KelpRect), KelpError> {
+        self.texture_cache.borrow().get_region(region)
+    }
+
+    /// Create a texture from RGBA data and slice it into frames on a grid
+    pub fn create_sprite_sheet_grid(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        grid: &SheetGrid,
+    ) -> Result<SpriteSheet, KelpError> {
+        SpriteSheet::from_grid(self, width, height, data, grid)
+    }
+
+    /// Create a texture from RGBA data and slice it into the frames of a TexturePacker or Aseprite JSON export,
+    /// in either the hash or array layout. Rotated frames are not supported.
+    pub fn create_sprite_sheet_json(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        json: &str,
+    ) -> Result<SpriteSheet, KelpError> {
+        SpriteSheet::from_json(self, width, height, data, json)
+    }
+
+    /// Create a texture of palette indices, one byte per pixel, drawn by setting `InstanceData::palette`.
+    /// Index 0 is not treated specially, so give the palette a transparent first colour if needed.
+    /// Indexed textures are kept in a single channel atlas of their own, which is bound alongside the RGBA atlas so
+    /// they still batch with other sprites. Drawn without a palette, they show their indices as shades of grey.
+    pub fn create_indexed_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        indices: &[u8],
+    ) -> Result<KelpTextureId, KelpError> {
+        self.check_texture_size(width, height)?;
+        if indices.len() as u64 != width as u64 * height as u64 {
+            return Err(KelpError::InvalidDataLength);
+        }
+        let texture_id = self.texture_cache.get_mut().new_indexed_alloc(width, height).ok_or(KelpError::AtlasFull)?;
+        let min = self.texture_cache.get_mut().get_texture(texture_id)?.rectangle.min;
+        self.write_texture_level(&self.index_atlas, 0, (min, 0), (width, height), indices, width);
+        Ok(texture_id)
     }
 
     pub fn render_imgui(&mut self, draw_data: &DrawData) -> Result<(), KelpError> {
         if self.imgui_renderer.is_none() {
             Err(KelpError::NoImgui)
         } else {
-            _ = self.per_frame.get_or_try_init(|| self.init_per_frame())?;
+            self.start_frame()?;
             let frame = self.per_frame.get_mut().unwrap();
             let encoder_desc = &wgpu::CommandEncoderDescriptor { label: Some("Kelp Imgui Commands") };
             let mut encoder = self.device.create_command_encoder(encoder_desc);
@@ -406,10 +547,168 @@ impl Kelp {
         }
     }
 
+    /// Set or remove the low resolution canvas, which then replaces the surface as the default render target.
+    /// The canvas is drawn to a texture of its size, so it must be at least 1x1 and within the device limits.
+    pub fn set_canvas(&mut self, config: Option<CanvasConfig>) -> Result<(), KelpError> {
+        if let Some(canvas) = &config {
+            self.check_texture_size(canvas.width, canvas.height)?;
+        }
+        self.canvas = config;
+        self.update_scene_size();
+        Ok(())
+    }
+
+    /// Compile a GLSL fragment shader for use with `PostEffect::Custom`.
+    /// It receives `fsin_TextureUV` at location 0, and may use the same bindings and push constant block as the
+    /// built in effects: `Source` (0), `PointSampler` (1), `LinearSampler` (2), `Aux` (3) and `PostBlock`.
+    pub fn create_post_shader(&mut self, source: &str) -> Result<KelpShaderId, KelpError> {
+        self.post_process.create_shader(&self.device, source)
+    }
+
+    /// Set the effects applied in order to the default render target, just before it is presented
+    pub fn set_post_effects(&mut self, effects: &[PostEffect]) -> Result<(), KelpError> {
+        self.post_process.set_effects(effects, &self.palette_cache)?;
+        self.update_scene_size();
+        Ok(())
+    }
+
+    /// Turn the FFI safe description of an effect into one for `set_post_effects`
+    pub fn post_effect_from_desc(&self, desc: &PostEffectDesc) -> Result<PostEffect, KelpError> {
+        self.post_process.effect_from_desc(desc)
+    }
+
+    /// Create a colour grading LUT from RGBA data, laid out as `size` square slices of increasing blue, each with
+    /// red increasing to the right and green increasing downwards, e.g. a 256x16 strip for a size of 16
+    pub fn create_lut(&mut self, size: u32, data: &[u8]) -> Result<KelpLutId, KelpError> {
+        self.post_process.create_lut(&self.device, &self.queue, size, data)
+    }
+
+    /// Free a LUT, removing any post effects that use it
+    pub fn remove_lut(&mut self, lut: KelpLutId) -> Result<(), KelpError> {
+        self.post_process.remove_lut(lut)?;
+        self.update_scene_size();
+        Ok(())
+    }
+
+    /// Create a palette from up to 256 RGBA colours
+    pub fn create_palette(&mut self, colors: &[u8]) -> Result<KelpPaletteId, KelpError> {
+        self.palette_cache.add_palette(&self.queue, colors)
+    }
+
+    /// Replace the colours of a palette, which may change how many it has
+    pub fn update_palette(&mut self, palette: KelpPaletteId, colors: &[u8]) -> Result<(), KelpError> {
+        self.palette_cache.update_palette(&self.queue, palette, colors)
+    }
+
+    /// Load a TrueType or OpenType font from the contents of its file
+    pub fn load_font(&mut self, data: &[u8]) -> Result<KelpFontId, KelpError> {
+        self.font_cache.add_font(data)
+    }
+
+    /// Load a BMFont (AngelCode) font from the contents of its `.fnt` file, in the text or binary format,
+    /// and the image files of its pages in order, which need the `image-png` feature for the usual PNG pages.
+    /// Bitmap fonts are drawn with the same text functions.
+    pub fn load_bitmap_font(&mut self, fnt: &[u8], pages: &[&[u8]]) -> Result<KelpFontId, KelpError> {
+        let mut font = BitmapFont::parse(fnt)?;
+        if pages.len() < font.page_files.len() {
+            return Err(KelpError::FontError(format!("expected {} pages", font.page_files.len())));
+        }
+        // Decode every page before creating any textures, and free those created if a later one fails
+        let images = pages.iter().map(|page| decode_image(page)).collect::<Result<Vec<_>, _>>()?;
+        for image in images {
+            let (width, height) = (image.width, image.height);
+            match self.create_texture_from_image(image) {
+                Ok(texture) => font.pages.push((texture, width, height)),
+                Err(err) => {
+                    for (texture, ..) in font.pages {
+                        self.remove_texture(texture)?;
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(self.font_cache.add_bitmap_font(font))
+    }
+
+    /// Load a BMFont (AngelCode) font from a `.fnt` file, finding its pages relative to it
+    pub fn load_bitmap_font_from_path(&mut self, path: impl AsRef<Path>) -> Result<KelpFontId, KelpError> {
+        let fnt = std::fs::read(path.as_ref())?;
+        let directory = path.as_ref().parent().unwrap_or(Path::new(""));
+        let pages = BitmapFont::parse(&fnt)?
+            .page_files
+            .iter()
+            .map(|file| std::fs::read(directory.join(file)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let pages: Vec<&[u8]> = pages.iter().map(Vec::as_slice).collect();
+        self.load_bitmap_font(&fnt, &pages)
+    }
+
+    /// Create instances for text in a single colour and size, with its top left at `position`.
+    /// Glyphs are rasterised into the atlas as needed, and stay cached while they are drawn every frame.
+    pub fn text_instances(
+        &mut self,
+        font: KelpFontId,
+        size: f32,
+        text: &str,
+        position: mint::Vector2<f32>,
+        color: mint::Vector4<f32>,
+    ) -> Result<TextInstances, KelpError> {
+        self.layout_text(font, &[TextSpan { text, color, size }], position, &TextLayout::default())
+    }
+
+    /// Lay out spans of text with wrapping and alignment, creating instances with its top left at `position`.
+    /// The returned instances include the measured size of the text.
+    pub fn layout_text(
+        &mut self,
+        font: KelpFontId,
+        spans: &[TextSpan],
+        position: mint::Vector2<f32>,
+        layout: &TextLayout,
+    ) -> Result<TextInstances, KelpError> {
+        self.font_cache.layout_text(
+            self.texture_cache.get_mut(),
+            &self.queue,
+            &self.texture_array,
+            font,
+            spans,
+            position,
+            layout,
+        )
+    }
+
+    /// Map a position in window pixels to canvas pixels, or return it unchanged if there is no canvas or the window
+    /// has no area to show it in, such as while minimised
+    pub fn window_to_canvas(&self, x: f32, y: f32) -> mint::Vector2<f32> {
+        let (width, height) = (self.window_surface_config.width, self.window_surface_config.height);
+        let canvas = self.canvas.map(|canvas| (canvas, canvas.viewport(width, height)));
+        match canvas {
+            Some((canvas, viewport)) if viewport.width > 0.0 && viewport.height > 0.0 => mint::Vector2 {
+                x: (x - viewport.x) * canvas.width as f32 / viewport.width,
+                y: (y - viewport.y) * canvas.height as f32 / viewport.height,
+            },
+            _ => mint::Vector2 { x, y },
+        }
+    }
+
+    /// Map a position in canvas pixels to window pixels, or return it unchanged if there is no canvas
+    pub fn canvas_to_window(&self, x: f32, y: f32) -> mint::Vector2<f32> {
+        match &self.canvas {
+            Some(canvas) => {
+                let viewport = canvas.viewport(self.window_surface_config.width, self.window_surface_config.height);
+                mint::Vector2 {
+                    x: viewport.x + x * viewport.width / canvas.width as f32,
+                    y: viewport.y + y * viewport.height / canvas.height as f32,
+                }
+            }
+            None => mint::Vector2 { x, y },
+        }
+    }
+
     pub fn set_surface_size(&mut self, width: u32, height: u32) {
         self.window_surface_config.width = width;
         self.window_surface_config.height = height;
         self.window_surface.configure(&self.device, &self.window_surface_config);
+        self.update_scene_size();
     }
 
     pub fn update_buffer<T: NoUninit>(&self, buffer: &wgpu::Buffer, data: &[T]) {
@@ -418,30 +717,443 @@ impl Kelp {
         self.queue.write_buffer(buffer, 0, bytes);
     }
 
+    /// Replace the whole of a texture with tightly packed RGBA data, which must be exactly its size
     pub fn update_texture(&self, texture_id: KelpTextureId, data: &[u8]) -> Result<(), KelpError> {
-        // TODO: check that the data length is correct for tex dims
+        let rectangle = self.texture_cache.borrow().get_texture(texture_id)?.rectangle;
+        let (width, height) = (rectangle.width() as u32, rectangle.height() as u32);
+        self.update_texture_region(texture_id, 0, 0, width, height, data, None)
+    }
+
+    /// Replace the whole of a texture with data in another format, which must be exactly its size
+    pub fn update_texture_with_format(
+        &self,
+        texture_id: KelpTextureId,
+        data: &[u8],
+        format: UploadFormat,
+    ) -> Result<(), KelpError> {
+        let rectangle = self.texture_cache.borrow().get_texture(texture_id)?.rectangle;
+        let (width, height) = (rectangle.width() as u32, rectangle.height() as u32);
+        self.update_texture_region_with_format(texture_id, 0, 0, width, height, data, None, format)
+    }
+
+    /// Replace part of a texture with RGBA data, where `bytes_per_row` is the stride between rows of `data`, or
+    /// `None` if they are tightly packed. Updates of the whole texture also refresh its padding and mipmaps, while
+    /// updates of part of it refresh the padding along the edges they touch. Smaller mip levels are made from the
+    /// whole image, so textures with mipmaps can only be updated whole.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_texture_region(
+        &self,
+        texture_id: KelpTextureId,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        bytes_per_row: Option<u32>,
+    ) -> Result<(), KelpError> {
+        let format = UploadFormat::default();
+        self.update_texture_region_with_format(texture_id, x, y, width, height, data, bytes_per_row, format)
+    }
+
+    /// Replace part of a texture like `update_texture_region`, with data in another format converted to RGBA first
+    /// Indexed textures keep one byte per pixel, so are only updated with indices in `PixelFormat::Gray8`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_texture_region_with_format(
+        &self,
+        texture_id: KelpTextureId,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        bytes_per_row: Option<u32>,
+        format: UploadFormat,
+    ) -> Result<(), KelpError> {
         let allocation = self.texture_cache.borrow().get_texture(texture_id)?;
-        let copy_texture = wgpu::ImageCopyTexture {
-            texture: self.texture_array.as_ref(),
-            mip_level: 0,
+        let layer = texture_id.array_layer();
+        let (texture_width, texture_height) =
+            (allocation.rectangle.width() as u32, allocation.rectangle.height() as u32);
+        if x.checked_add(width).is_none_or(|right| right > texture_width)
+            || y.checked_add(height).is_none_or(|bottom| bottom > texture_height)
+        {
+            return Err(KelpError::RegionOutOfBounds);
+        }
+        if texture_id.is_indexed() && format.pixels != PixelFormat::Gray8 {
+            return Err(KelpError::IndexedTextureFormat);
+        }
+        let whole = width == texture_width && height == texture_height;
+        if !whole && allocation.mip_levels > 1 {
+            return Err(KelpError::PartialMipmapUpdate);
+        }
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let mut stride = row_stride(data, (width, height), format.pixels.bytes_per_pixel(), bytes_per_row)?;
+        let min = allocation.rectangle.min + guillotiere::size2(x as i32, y as i32);
+        if texture_id.is_indexed() {
+            self.write_texture_level(&self.index_atlas, 0, (min, 0), (width, height), data, stride as u32);
+            return Ok(());
+        }
+        // Replaced content no longer matches the hash it was shared by
+        self.texture_cache.borrow_mut().forget_content(texture_id);
+        let texture_cache = self.texture_cache.borrow();
+        let texture = match texture_id.is_dedicated() {
+            true => &texture_cache.get_dedicated(texture_id)?.texture,
+            false => self.texture_array.as_ref(),
+        };
+        let converted: Vec<u8>;
+        let data = if format.is_rgba() {
+            data
+        } else {
+            converted = format.to_rgba(data, width, height, stride);
+            stride = 4 * width as usize;
+            &converted
+        };
+        let row_size = 4 * width as usize;
+
+        let padded = allocation.rectangle != allocation.outer && (allocation.extrude || allocation.mip_levels > 1);
+        // Only the edges of the texture have padding to fill
+        let (pad_left, pad_top) = (allocation.rectangle.min - allocation.outer.min).to_tuple();
+        let (pad_right, pad_bottom) = (allocation.outer.max - allocation.rectangle.max).to_tuple();
+        let touches = [x == 0, y == 0, x + width == texture_width, y + height == texture_height];
+        if !padded || (!whole && !(allocation.extrude && touches.contains(&true))) {
+            self.write_texture_level(texture, 0, (min, layer), (width, height), data, stride as u32);
+            return Ok(());
+        }
+
+        // Repack strided data, then surround it with its padding on the sides at the texture edges
+        let packed: Vec<u8>;
+        let data = if stride == row_size {
+            &data[..row_size * height as usize]
+        } else {
+            packed = data.chunks(stride).flat_map(|row| &row[..row_size]).copied().collect();
+            &packed
+        };
+        let grow = |touch: bool, pad: i32| if touch { pad } else { 0 };
+        let offset = (grow(touches[0], pad_left), grow(touches[1], pad_top));
+        let outer_min = min - guillotiere::size2(offset.0, offset.1);
+        let outer_size = (
+            width + (offset.0 + grow(touches[2], pad_right)) as u32,
+            height + (offset.1 + grow(touches[3], pad_bottom)) as u32,
+        );
+        let mut level_data =
+            pad_pixels(data, (width, height), outer_size, (offset.0 as u32, offset.1 as u32), allocation.extrude);
+        self.write_texture_level(texture, 0, (outer_min, layer), outer_size, &level_data, 4 * outer_size.0);
+        if !whole {
+            return Ok(());
+        }
+        for level in 1..allocation.mip_levels {
+            let size = (outer_size.0 >> (level - 1), outer_size.1 >> (level - 1));
+            level_data = downsample(&level_data, size, allocation.color_space);
+            let origin = guillotiere::point2(outer_min.x >> level, outer_min.y >> level);
+            let size = (outer_size.0 >> level, outer_size.1 >> level);
+            self.write_texture_level(texture, level, (origin, layer), size, &level_data, 4 * size.0);
+        }
+        Ok(())
+    }
+
+    /// Create a texture from data in another format, stored as RGBA in the given colour space
+    pub fn create_texture_with_format(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        format: UploadFormat,
+        color_space: ColorSpace,
+    ) -> Result<KelpTextureId, KelpError> {
+        // With deduplication on, identical content shares the texture created for it first
+        let key = self.texture_cache.get_mut().content_key((width, height), format, color_space);
+        if let Some(id) = key.and_then(|key| self.texture_cache.get_mut().share_texture(data, key)) {
+            return Ok(id);
+        }
+        let id = self.create_texture_empty(width, height)?;
+        let uploaded = self
+            .set_texture_color_space(id, color_space)
+            .and_then(|_| self.update_texture_with_format(id, data, format));
+        if let Err(err) = uploaded {
+            self.remove_texture(id)?;
+            return Err(err);
+        }
+        if let Some(key) = key {
+            self.texture_cache.get_mut().insert_content(data, key, id);
+        }
+        Ok(id)
+    }
+
+    /// Free a texture, or remove one reference to it if it is shared by deduplication, in which case it is only
+    /// freed with its last reference. Nothing drawn this frame may use a texture being freed.
+    pub fn remove_texture(&mut self, texture: KelpTextureId) -> Result<(), KelpError> {
+        self.texture_cache.get_mut().remove_texture(texture)
+    }
+
+    /// Set whether textures created with data after this share an existing texture with identical content, each
+    /// returning the same id, rather than using more atlas space. Updating a shared texture changes it for all of them.
+    pub fn set_texture_deduplication(&mut self, deduplicate: bool) {
+        self.texture_cache.get_mut().set_deduplicate(deduplicate);
+    }
+
+    /// Create a texture from the contents of a PNG file
+    #[cfg(feature = "image-png")]
+    pub fn create_texture_from_png(&mut self, data: &[u8]) -> Result<KelpTextureId, KelpError> {
+        self.create_texture_from_image(crate::decode_png(data)?)
+    }
+
+    /// Create a texture from the contents of a PNG, QOI or JPEG file, detecting which it is. Formats are each
+    /// supported with their feature, `image-png` (enabled by default), `image-qoi` or `image-jpeg`.
+    pub fn create_texture_from_memory(&mut self, data: &[u8]) -> Result<KelpTextureId, KelpError> {
+        self.create_texture_from_image(decode_image(data)?)
+    }
+
+    /// Create a texture from a PNG, QOI or JPEG file, like `create_texture_from_memory`
+    pub fn create_texture_from_path(&mut self, path: impl AsRef<Path>) -> Result<KelpTextureId, KelpError> {
+        let data = std::fs::read(path)?;
+        self.create_texture_from_memory(&data)
+    }
+
+    /// Start loading a PNG, QOI or JPEG file on a worker thread, like `create_texture_from_path`. The texture is
+    /// created at the start of the first frame after it is decoded, and can be found with `loaded_texture`.
+    pub fn load_texture_async(&mut self, path: impl Into<PathBuf>) -> KelpLoadId {
+        self.texture_loader.queue(LoadSource::Path(path.into()))
+    }
+
+    /// Start decoding the contents of an image file on a worker thread, like `load_texture_async`
+    pub fn load_texture_async_from_memory(&mut self, data: Vec<u8>) -> KelpLoadId {
+        self.texture_loader.queue(LoadSource::Memory(data))
+    }
+
+    /// Get the texture of a background load, or `None` while it is still loading. Finished loads return their
+    /// texture or error once, after which their id is no longer valid.
+    pub fn loaded_texture(&mut self, load: KelpLoadId) -> Result<Option<KelpTextureId>, KelpError> {
+        self.texture_loader.state(load)
+    }
+
+    /// How many background loads have finished out of those started since the last time none were loading
+    pub fn load_progress(&self) -> LoadProgress {
+        self.texture_loader.progress()
+    }
+
+    /// Get the width and height of a texture in pixels
+    pub fn texture_size(&self, texture: KelpTextureId) -> Result<(u32, u32), KelpError> {
+        let rectangle = self.texture_cache.borrow().get_texture(texture)?.rectangle;
+        Ok((rectangle.width() as u32, rectangle.height() as u32))
+    }
+
+    /// Set whether a texture holds sRGB colours or linear values, which for mipmapped textures must be done before
+    /// uploading to it, as its smaller levels are made in that colour space
+    pub fn set_texture_color_space(
+        &mut self,
+        texture: KelpTextureId,
+        color_space: ColorSpace,
+    ) -> Result<(), KelpError> {
+        self.texture_cache.get_mut().set_color_space(texture, color_space)
+    }
+
+    fn create_texture_from_image(&mut self, image: DecodedImage) -> Result<KelpTextureId, KelpError> {
+        let format = UploadFormat { pixels: image.pixels, ..Default::default() };
+        self.create_texture_with_format(image.width, image.height, &image.data, format, image.color_space)
+    }
+
+    /// Create a texture of its own outside the atlas, for content replaced every frame such as video or software
+    /// rendering. Updates go straight to it without padding or mipmaps, and strided data is uploaded without a copy.
+    /// It is drawn in batches of its own, so is best kept to few large textures.
+    pub fn create_texture_dynamic(&mut self, width: u32, height: u32) -> Result<KelpTextureId, KelpError> {
+        self.check_texture_size(width, height)?;
+        Ok(self.create_dedicated_texture(width, height))
+    }
+
+    /// Set the padding in pixels around textures created after this, and whether uploads extrude their edge pixels
+    /// into it. Padding keeps linear filtering and wrapping from reaching neighbouring textures in the atlas, and
+    /// extruding stops transparent padding from showing as dark fringes. The default is one extruded pixel.
+    pub fn set_atlas_padding(&mut self, padding: u32, extrude: bool) {
+        self.texture_cache.get_mut().set_padding(padding, extrude);
+    }
+
+    /// Set how many mip levels textures created after this have, from 1 for none up to 4. Mipmaps are used by smooth
+    /// instances that set `mipmaps`, to reduce aliasing when drawn smaller. Mipmapped textures are padded by at least
+    /// a texel of their smallest level and aligned to its texels, so no level blends in neighbouring textures.
+    /// The atlas only gets room for mip levels the first time they are turned on, which adds a third to its memory and
+    /// copies the textures already in it, so this is best called before creating any.
+    pub fn set_atlas_mipmaps(&mut self, mip_levels: u32) {
+        self.texture_cache.get_mut().set_mip_levels(mip_levels);
+        if mip_levels > 1 && self.texture_array.mip_level_count() == 1 {
+            self.add_atlas_mip_levels();
+        }
+    }
+
+    /// Repack the textures in the atlas to merge its free space, so larger textures fit again after many have been
+    /// removed. Texture and region ids stay valid, but render lists built before this must be built again, and it
+    /// cannot be done while a frame is being drawn. Layers where a texture would lose mip levels by moving are left as
+    /// they were, and counted as skipped in the report.
+    pub fn compact_atlas(&mut self) -> Result<AtlasCompaction, KelpError> {
+        if self.per_frame.get().is_some() {
+            return Err(KelpError::FrameInProgress);
+        }
+        let (moves, report) = self.texture_cache.get_mut().compact();
+        if moves.is_empty() {
+            return Ok(report);
+        }
+        // A texture cannot be copied to itself, so the moved textures are copied aside first and then to where they
+        // now belong. Only they are copied, packed together in a texture just large enough to hold them.
+        let atlas = self.texture_array.as_ref();
+        let align = 1 << (atlas.mip_level_count() - 1);
+        let sizes: Vec<_> =
+            moves.iter().map(|(old, _)| (old.outer.width() as u32, old.outer.height() as u32)).collect();
+        let (positions, size) = pack_rows(&sizes, (atlas.width(), atlas.height()), align);
+        let scratch = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Atlas Compaction Texture"),
+            size,
+            mip_level_count: atlas.mip_level_count(),
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: atlas.format(),
+            usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let encoder_desc = &wgpu::CommandEncoderDescriptor { label: Some("Kelp Atlas Compaction Commands") };
+        let mut encoder = self.device.create_command_encoder(encoder_desc);
+        let copy = |texture, mip_level, origin: guillotiere::Point, layer| wgpu::ImageCopyTexture {
+            texture,
+            mip_level,
             origin: wgpu::Origin3d {
-                x: allocation.rectangle.min.x as u32,
-                y: allocation.rectangle.min.y as u32,
-                z: allocation.id.layer,
+                x: origin.x as u32 >> mip_level,
+                y: origin.y as u32 >> mip_level,
+                z: layer,
             },
             aspect: wgpu::TextureAspect::All,
         };
-        let write_size = wgpu::Extent3d {
-            width: allocation.rectangle.width() as u32,
-            height: allocation.rectangle.height() as u32,
+        let level_size = |rectangle: guillotiere::Rectangle, level: u32| wgpu::Extent3d {
+            width: rectangle.width() as u32 >> level,
+            height: rectangle.height() as u32 >> level,
             depth_or_array_layers: 1,
         };
+        for ((old, _), &(origin, scratch_layer)) in moves.iter().zip(&positions) {
+            for level in 0..old.mip_levels {
+                encoder.copy_texture_to_texture(
+                    copy(atlas, level, old.outer.min, old.id.layer),
+                    copy(&scratch, level, origin, scratch_layer),
+                    level_size(old.outer, level),
+                );
+            }
+        }
+        for ((old, new), &(origin, scratch_layer)) in moves.iter().zip(&positions) {
+            for level in 0..new.mip_levels {
+                encoder.copy_texture_to_texture(
+                    copy(&scratch, level, origin, scratch_layer),
+                    copy(atlas, level, new.outer.min, new.id.layer),
+                    level_size(old.outer, level),
+                );
+            }
+        }
+        self.queue.submit([encoder.finish()]);
+        Ok(report)
+    }
+
+    /// Replace the atlas with one that has mip levels, copying the full size level that is all it has so far
+    fn add_atlas_mip_levels(&mut self) {
+        let atlas = create_atlas_texture(&self.device, self.texture_array.size(), ATLAS_MIP_LEVELS);
+        let encoder_desc = &wgpu::CommandEncoderDescriptor { label: Some("Kelp Atlas Mipmap Commands") };
+        let mut encoder = self.device.create_command_encoder(encoder_desc);
+        encoder.copy_texture_to_texture(
+            self.texture_array.as_image_copy(),
+            atlas.as_image_copy(),
+            self.texture_array.size(),
+        );
+        self.queue.submit([encoder.finish()]);
+        self.main_bind_group = create_sprite_bind_group(
+            &self.device,
+            self.pipeline_cache.bind_layout(),
+            &self.instance_buffer,
+            &atlas,
+            &self.samplers,
+            &self.palette_cache.create_view(),
+            &self.index_atlas,
+        );
+        self.texture_array = Rc::new(atlas);
+    }
+
+    /// Textures must have some size, and be no larger than the device supports
+    fn check_texture_size(&self, width: u32, height: u32) -> Result<(), KelpError> {
+        let max = self.device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 {
+            Err(KelpError::EmptyTexture)
+        } else if width > max || height > max {
+            Err(KelpError::TextureTooLarge { width, height, max })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Textures too large for the atlas are drawn from their own texture, in batches of their own
+    fn create_dedicated_texture(&mut self, width: u32, height: u32) -> KelpTextureId {
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Dedicated Texture"),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[wgpu::TextureFormat::Rgba8Unorm],
+        });
+        let bind_group = create_sprite_bind_group(
+            &self.device,
+            self.pipeline_cache.bind_layout(),
+            &self.instance_buffer,
+            &texture,
+            &self.samplers,
+            &self.palette_cache.create_view(),
+            &self.index_atlas,
+        );
+        self.texture_cache.get_mut().insert_dedicated(texture, bind_group)
+    }
+
+    fn write_texture_level(
+        &self,
+        texture: &wgpu::Texture,
+        level: u32,
+        (origin, layer): (guillotiere::Point, u32),
+        (width, height): (u32, u32),
+        data: &[u8],
+        bytes_per_row: u32,
+    ) {
+        let copy_texture = wgpu::ImageCopyTexture {
+            texture,
+            mip_level: level,
+            origin: wgpu::Origin3d { x: origin.x as u32, y: origin.y as u32, z: layer },
+            aspect: wgpu::TextureAspect::All,
+        };
+        let write_size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
         let data_layout = wgpu::ImageDataLayout {
             offset: 0,
-            bytes_per_row: Some(4 * write_size.width),
-            rows_per_image: Some(write_size.height),
+            bytes_per_row: Some(bytes_per_row),
+            rows_per_image: Some(height),
         };
         self.queue.write_texture(copy_texture, data, data_layout, write_size);
+    }
+
+    /// The scene texture is needed at the canvas size if there is one, or the surface size for post effects alone
+    fn update_scene_size(&mut self) {
+        let size = match &self.canvas {
+            Some(canvas) => Some((canvas.width, canvas.height)),
+            None if self.post_process.has_effects() => {
+                Some((self.window_surface_config.width, self.window_surface_config.height))
+            }
+            None => None,
+        };
+        self.post_process.set_scene_size(&self.device, size);
+    }
+
+    /// Initialise per frame resources if the frame has not begun yet, first uploading any textures that have
+    /// finished loading in the background so they can be drawn from this frame on
+    fn start_frame(&mut self) -> Result<(), KelpError> {
+        if self.per_frame.get().is_none() {
+            for (load, image) in self.texture_loader.take_decoded() {
+                let texture = image.and_then(|image| self.create_texture_from_image(image));
+                self.texture_loader.finish(load, texture);
+            }
+        }
+        _ = self.per_frame.get_or_try_init(|| self.init_per_frame())?;
         Ok(())
     }
 
@@ -460,3 +1172,74 @@ impl Kelp {
         })
     }
 }
+
+fn create_atlas_texture(device: &wgpu::Device, size: wgpu::Extent3d, mip_level_count: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size,
+        mip_level_count,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST,
+        // Indexed textures are read without the sRGB conversion
+        view_formats: &[wgpu::TextureFormat::Rgba8Unorm],
+    })
+}
+
+/// Bind the instances, a texture array to sample and the shared samplers, palettes and index atlas for drawing sprites
+fn create_sprite_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    instance_buffer: &wgpu::Buffer,
+    texture: &wgpu::Texture,
+    [point_sampler, linear_sampler, trilinear_sampler]: &[wgpu::Sampler; 3],
+    palette_view: &wgpu::TextureView,
+    index_atlas: &wgpu::Texture,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Sprite Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: instance_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&texture.create_view(&wgpu::TextureViewDescriptor {
+                    dimension: Some(wgpu::TextureViewDimension::D2Array),
+                    ..Default::default()
+                })),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(point_sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::Sampler(linear_sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(&texture.create_view(&wgpu::TextureViewDescriptor {
+                    format: Some(wgpu::TextureFormat::Rgba8Unorm),
+                    dimension: Some(wgpu::TextureViewDimension::D2Array),
+                    ..Default::default()
+                })),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::TextureView(palette_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: wgpu::BindingResource::Sampler(trilinear_sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: wgpu::BindingResource::TextureView(&index_atlas.create_view(&wgpu::TextureViewDescriptor {
+                    dimension: Some(wgpu::TextureViewDimension::D2Array),
+                    ..Default::default()
+                })),
+            },
+        ],
+    })
+}
@@ -1,14 +1,38 @@
 #![feature(once_cell_try)]
 
+mod animation;
+mod bitmap_font;
+mod canvas;
+mod font_cache;
+mod image_loader;
 mod kelp;
+mod nine_slice;
+mod palette_cache;
 mod pipeline_cache;
+mod pixel_format;
+mod post_process;
 mod render_list;
+mod sprite_batch;
+mod sprite_sheet;
+mod text_layout;
 mod texture_cache;
+mod texture_loader;
 mod types;
 
+pub use animation::*;
 pub use kelp::*;
 pub use render_list::*;
+pub use sprite_batch::*;
 pub use types::*;
 
+pub(crate) use bitmap_font::*;
+pub(crate) use canvas::*;
+pub(crate) use font_cache::*;
+pub(crate) use image_loader::*;
+pub(crate) use palette_cache::*;
 pub(crate) use pipeline_cache::*;
+pub(crate) use pixel_format::*;
+pub(crate) use post_process::*;
+pub(crate) use text_layout::*;
 pub(crate) use texture_cache::*;
+pub(crate) use texture_loader::*;
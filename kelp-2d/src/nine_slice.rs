@@ -0,0 +1,187 @@
+use crate::{InstanceData, Kelp, KelpError, KelpRect, KelpTextureId, NineSlice, NineSliceFill};
+
+/// Part of one axis of a nine-slice, from an offset and length in the region to an offset and length on screen
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    source: f32,
+    source_len: f32,
+    dest: f32,
+    dest_len: f32,
+}
+
+impl NineSlice {
+    /// Build the instances that draw this nine-slice over `rect`, with the texture to draw them from.
+    /// If the rectangle is smaller than the borders, the borders shrink to fit.
+    pub fn instances(
+        &self,
+        kelp: &Kelp,
+        rect: KelpRect,
+        color: mint::Vector4<f32>,
+    ) -> Result<(KelpTextureId, Vec<InstanceData>), KelpError> {
+        let (texture, region) = kelp.region(self.region)?;
+        self.check_insets(&region)?;
+
+        let columns = axis(region.width, rect.width, self.left, self.right);
+        let rows = axis(region.height, rect.height, self.top, self.bottom);
+        let mut instances = Vec::new();
+        for (row, y) in rows.iter().enumerate() {
+            for (column, x) in columns.iter().enumerate() {
+                // Edges only repeat along their length, the centre along both axes
+                let fill = |middle: usize, other: usize| match (middle, other) {
+                    (1, 1) => self.center,
+                    (1, _) => self.edges,
+                    _ => NineSliceFill::Stretch,
+                };
+                for x in fill_span(*x, fill(column, row)) {
+                    for y in fill_span(*y, fill(row, column)) {
+                        instances.push(InstanceData {
+                            color,
+                            source_trans: [x.source, y.source].into(),
+                            source_scale: [x.source_len / region.width, y.source_len / region.height].into(),
+                            world: mint::RowMatrix3x2 {
+                                x: [x.dest_len, 0.0].into(),
+                                y: [0.0, y.dest_len].into(),
+                                z: [rect.x + x.dest, rect.y + y.dest].into(),
+                            },
+                            region: Some(self.region),
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+        }
+        Ok((texture, instances))
+    }
+
+    /* private */
+    /// The insets must not be negative, or overlap within the region
+    fn check_insets(&self, region: &KelpRect) -> Result<(), KelpError> {
+        let insets = [self.left, self.top, self.right, self.bottom];
+        if insets.iter().any(|inset| *inset < 0.0)
+            || self.left + self.right > region.width
+            || self.top + self.bottom > region.height
+        {
+            return Err(KelpError::InvalidNineSlice);
+        }
+        Ok(())
+    }
+}
+
+/// Split one axis into its start border, middle and end border
+fn axis(source_len: f32, dest_len: f32, start: f32, end: f32) -> [Span; 3] {
+    let shrink = if start + end > dest_len && start + end > 0.0 {
+        dest_len / (start + end)
+    } else {
+        1.0
+    };
+    let (dest_start, dest_end) = (start * shrink, end * shrink);
+    [
+        Span {
+            source: 0.0,
+            source_len: start,
+            dest: 0.0,
+            dest_len: dest_start,
+        },
+        Span {
+            source: start,
+            source_len: source_len - start - end,
+            dest: dest_start,
+            dest_len: dest_len - dest_start - dest_end,
+        },
+        Span {
+            source: source_len - end,
+            source_len: end,
+            dest: dest_len - dest_end,
+            dest_len: dest_end,
+        },
+    ]
+}
+
+/// The pieces a span is drawn with, leaving out any with nothing to draw
+fn fill_span(span: Span, fill: NineSliceFill) -> Vec<Span> {
+    if span.source_len <= 0.0 || span.dest_len <= 0.0 {
+        return Vec::new();
+    }
+    match fill {
+        NineSliceFill::Stretch => vec![span],
+        NineSliceFill::Tile => {
+            let count = (span.dest_len / span.source_len).ceil() as usize;
+            (0..count)
+                .map(|index| {
+                    let offset = index as f32 * span.source_len;
+                    let len = span.source_len.min(span.dest_len - offset);
+                    Span {
+                        source: span.source,
+                        source_len: len,
+                        dest: span.dest + offset,
+                        dest_len: len,
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KelpRegionId;
+
+    fn spans(spans: &[Span]) -> Vec<(f32, f32, f32, f32)> {
+        spans.iter().map(|span| (span.source, span.source_len, span.dest, span.dest_len)).collect()
+    }
+
+    fn nine_slice(left: f32, top: f32, right: f32, bottom: f32) -> NineSlice {
+        NineSlice {
+            region: KelpRegionId(0),
+            left,
+            top,
+            right,
+            bottom,
+            edges: NineSliceFill::Stretch,
+            center: NineSliceFill::Stretch,
+        }
+    }
+
+    #[test]
+    fn keeps_borders_and_stretches_the_middle() {
+        let axis = axis(16.0, 100.0, 4.0, 6.0);
+        assert_eq!(spans(&axis), [(0.0, 4.0, 0.0, 4.0), (4.0, 6.0, 4.0, 90.0), (10.0, 6.0, 94.0, 6.0)]);
+    }
+
+    #[test]
+    fn shrinks_borders_larger_than_the_destination() {
+        let axis = axis(16.0, 5.0, 4.0, 6.0);
+        assert_eq!(spans(&axis), [(0.0, 4.0, 0.0, 2.0), (4.0, 6.0, 2.0, 0.0), (10.0, 6.0, 2.0, 3.0)]);
+        // Nothing is drawn for the middle once the borders fill the space
+        assert!(fill_span(axis[1], NineSliceFill::Stretch).is_empty());
+    }
+
+    #[test]
+    fn handles_zero_insets() {
+        let axis = axis(16.0, 40.0, 0.0, 0.0);
+        assert_eq!(spans(&axis[1..2]), [(0.0, 16.0, 0.0, 40.0)]);
+        assert!(fill_span(axis[0], NineSliceFill::Stretch).is_empty());
+        assert!(fill_span(axis[2], NineSliceFill::Tile).is_empty());
+        let empty = super::axis(16.0, 0.0, 0.0, 0.0);
+        assert!(empty.iter().all(|span| fill_span(*span, NineSliceFill::Stretch).is_empty()));
+    }
+
+    #[test]
+    fn tiles_at_the_source_size_cutting_the_last_short() {
+        let middle = axis(16.0, 30.0, 4.0, 4.0)[1];
+        let tiles = fill_span(middle, NineSliceFill::Tile);
+        assert_eq!(spans(&tiles), [(4.0, 8.0, 4.0, 8.0), (4.0, 8.0, 12.0, 8.0), (4.0, 6.0, 20.0, 6.0)]);
+    }
+
+    #[test]
+    fn rejects_negative_or_oversized_insets() {
+        let region = KelpRect { x: 0.0, y: 0.0, width: 16.0, height: 8.0 };
+        assert!(nine_slice(4.0, 2.0, 4.0, 2.0).check_insets(&region).is_ok());
+        assert!(nine_slice(0.0, 0.0, 0.0, 0.0).check_insets(&region).is_ok());
+        assert!(nine_slice(8.0, 4.0, 8.0, 4.0).check_insets(&region).is_ok());
+        assert!(nine_slice(-1.0, 2.0, 4.0, 2.0).check_insets(&region).is_err());
+        assert!(nine_slice(10.0, 2.0, 7.0, 2.0).check_insets(&region).is_err());
+        assert!(nine_slice(4.0, 5.0, 4.0, 4.0).check_insets(&region).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{KelpError, KelpPaletteId};
+
+/// Maximum number of colours in a single palette
+pub(crate) const PALETTE_SIZE: u32 = 256;
+/// Maximum number of palettes, each stored as one row of the palette texture
+pub(crate) const PALETTE_ROWS: u32 = 256;
+
+/// Stores every palette as a row of one texture, so they can all be bound at once
+#[derive(Debug)]
+pub(crate) struct PaletteCache {
+    texture: wgpu::Texture,
+    color_counts: Vec<u32>,
+}
+
+impl PaletteCache {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Palette Texture"),
+            size: wgpu::Extent3d {
+                width: PALETTE_SIZE,
+                height: PALETTE_ROWS,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        Self { texture, color_counts: Vec::new() }
+    }
+
+    pub fn add_palette(&mut self, queue: &wgpu::Queue, colors: &[u8]) -> Result<KelpPaletteId, KelpError> {
+        if self.color_counts.len() as u32 >= PALETTE_ROWS {
+            return Err(KelpError::TooManyPalettes);
+        }
+        let id = KelpPaletteId(self.color_counts.len() as u32);
+        self.color_counts.push(0);
+        self.update_palette(queue, id, colors)?;
+        Ok(id)
+    }
+
+    pub fn update_palette(&mut self, queue: &wgpu::Queue, id: KelpPaletteId, colors: &[u8]) -> Result<(), KelpError> {
+        let color_count = colors.len() as u32 / 4;
+        if !colors.len().is_multiple_of(4) || color_count == 0 || color_count > PALETTE_SIZE {
+            return Err(KelpError::InvalidDataLength);
+        }
+        let count = self.color_counts.get_mut(id.0 as usize).ok_or(KelpError::InvalidPaletteId)?;
+        *count = color_count;
+        let copy_texture = wgpu::ImageCopyTexture {
+            texture: &self.texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x: 0, y: id.0, z: 0 },
+            aspect: wgpu::TextureAspect::All,
+        };
+        let data_layout = wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(colors.len() as u32),
+            rows_per_image: None,
+        };
+        let write_size = wgpu::Extent3d { width: color_count, height: 1, depth_or_array_layers: 1 };
+        queue.write_texture(copy_texture, colors, data_layout, write_size);
+        Ok(())
+    }
+
+    pub fn color_count(&self, id: KelpPaletteId) -> Result<u32, KelpError> {
+        self.color_counts.get(id.0 as usize).copied().ok_or(KelpError::InvalidPaletteId)
+    }
+
+    pub fn create_view(&self) -> wgpu::TextureView {
+        self.texture.create_view(&Default::default())
+    }
+}
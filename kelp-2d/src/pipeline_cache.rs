@@ -63,6 +63,10 @@ impl PipelineCache {
         Ok(())
     }
 
+    pub fn bind_layout(&self) -> &BindGroupLayout {
+        &self.vertex_bind_layout
+    }
+
     pub fn get_pipeline_index(&self, shader: Option<&ShaderModule>, blend_mode: BlendMode) -> Result<usize, KelpError> {
         let id = Self::to_pipeline_id(shader, blend_mode);
         self.cache.get_index_of(&id).ok_or(KelpError::InvalidPipelineId)
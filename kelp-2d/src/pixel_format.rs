@@ -0,0 +1,129 @@
+use crate::{AlphaMode, KelpError, PixelFormat, UploadFormat};
+
+/// Check that data holds rows of pixels for an update, returning the stride between them in bytes.
+/// Packed data must be exactly the size of the update, and strided data must reach the end of its last row.
+pub(crate) fn row_stride(
+    data: &[u8],
+    (width, height): (u32, u32),
+    bytes_per_pixel: u32,
+    bytes_per_row: Option<u32>,
+) -> Result<usize, KelpError> {
+    let row_size = (bytes_per_pixel * width) as usize;
+    let stride = bytes_per_row.map_or(row_size, |stride| stride as usize);
+    if stride < row_size {
+        return Err(KelpError::InvalidRowStride);
+    }
+    let expected = stride * (height as usize).saturating_sub(1) + row_size;
+    let too_long = match bytes_per_row {
+        Some(_) => data.len() > stride * height as usize,
+        None => data.len() > expected,
+    };
+    if data.len() < expected || too_long {
+        return Err(KelpError::TextureDataSize { expected, actual: data.len() });
+    }
+    Ok(stride)
+}
+
+impl UploadFormat {
+    /// Whether data in this format can be uploaded without converting it
+    pub(crate) fn is_rgba(self) -> bool {
+        self.pixels == PixelFormat::Rgba8 && self.alpha == AlphaMode::Straight
+    }
+
+    /// Convert rows of pixels `stride` bytes apart to tightly packed, straight alpha RGBA
+    pub(crate) fn to_rgba(self, data: &[u8], width: u32, height: u32, stride: usize) -> Vec<u8> {
+        let row_size = (self.pixels.bytes_per_pixel() * width) as usize;
+        let mut rgba = Vec::with_capacity((4 * width * height) as usize);
+        for row in data.chunks(stride).take(height as usize) {
+            let row = &row[..row_size];
+            match self.pixels {
+                PixelFormat::Rgba8 => rgba.extend_from_slice(row),
+                PixelFormat::Bgra8 => {
+                    rgba.extend(row.chunks_exact(4).flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]]))
+                }
+                PixelFormat::Rgb8 => rgba.extend(row.chunks_exact(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])),
+                PixelFormat::Gray8 => rgba.extend(row.iter().flat_map(|&gray| [gray, gray, gray, 255])),
+                PixelFormat::GrayAlpha8 => rgba.extend(row.chunks_exact(2).flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])),
+            }
+        }
+        if self.alpha == AlphaMode::Premultiplied {
+            for pixel in rgba.chunks_exact_mut(4) {
+                let alpha = pixel[3] as u32;
+                for channel in &mut pixel[..3] {
+                    *channel = match alpha {
+                        0 => 0,
+                        _ => ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8,
+                    };
+                }
+            }
+        }
+        rgba
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(pixels: PixelFormat, alpha: AlphaMode) -> UploadFormat {
+        UploadFormat { pixels, alpha }
+    }
+
+    #[test]
+    fn accepts_packed_rows() {
+        assert_eq!(row_stride(&[0; 24], (3, 2), 4, None).unwrap(), 12);
+        assert!(matches!(
+            row_stride(&[0; 23], (3, 2), 4, None),
+            Err(KelpError::TextureDataSize { expected: 24, actual: 23 })
+        ));
+        assert!(row_stride(&[0; 25], (3, 2), 4, None).is_err());
+    }
+
+    #[test]
+    fn accepts_strided_rows_with_or_without_the_last_padding() {
+        // The last row only has to reach its own end, or may include the padding of a full stride
+        assert_eq!(row_stride(&[0; 28], (3, 2), 4, Some(16)).unwrap(), 16);
+        assert_eq!(row_stride(&[0; 32], (3, 2), 4, Some(16)).unwrap(), 16);
+    }
+
+    #[test]
+    fn rejects_short_and_over_long_strided_rows() {
+        assert!(matches!(row_stride(&[0; 27], (3, 2), 4, Some(16)), Err(KelpError::TextureDataSize { .. })));
+        assert!(matches!(row_stride(&[0; 33], (3, 2), 4, Some(16)), Err(KelpError::TextureDataSize { .. })));
+        assert!(matches!(row_stride(&[0; 24], (3, 2), 4, Some(8)), Err(KelpError::InvalidRowStride)));
+    }
+
+    #[test]
+    fn converts_pixel_formats() {
+        let rgba = |pixels, data: &[u8]| format(pixels, AlphaMode::Straight).to_rgba(data, 1, 1, data.len());
+        assert_eq!(rgba(PixelFormat::Rgba8, &[1, 2, 3, 4]), [1, 2, 3, 4]);
+        assert_eq!(rgba(PixelFormat::Bgra8, &[1, 2, 3, 4]), [3, 2, 1, 4]);
+        assert_eq!(rgba(PixelFormat::Rgb8, &[1, 2, 3]), [1, 2, 3, 255]);
+        assert_eq!(rgba(PixelFormat::Gray8, &[7]), [7, 7, 7, 255]);
+        assert_eq!(rgba(PixelFormat::GrayAlpha8, &[7, 9]), [7, 7, 7, 9]);
+    }
+
+    #[test]
+    fn skips_row_padding() {
+        let data = [1, 2, 3, 0, 4, 5, 6];
+        let rgba = format(PixelFormat::Rgb8, AlphaMode::Straight).to_rgba(&data, 1, 2, 4);
+        assert_eq!(rgba, [1, 2, 3, 255, 4, 5, 6, 255]);
+    }
+
+    #[test]
+    fn unpremultiplies_alpha() {
+        let premultiplied = format(PixelFormat::Rgba8, AlphaMode::Premultiplied);
+        assert_eq!(premultiplied.to_rgba(&[64, 32, 0, 128], 1, 1, 4), [128, 64, 0, 128]);
+        assert_eq!(premultiplied.to_rgba(&[255, 255, 255, 255], 1, 1, 4), [255, 255, 255, 255]);
+        // Fully transparent pixels have no colour to recover, and brighter than alpha is clamped
+        assert_eq!(premultiplied.to_rgba(&[10, 20, 30, 0], 1, 1, 4), [0, 0, 0, 0]);
+        assert_eq!(premultiplied.to_rgba(&[200, 0, 0, 100], 1, 1, 4), [255, 0, 0, 100]);
+    }
+
+    #[test]
+    fn only_straight_rgba_skips_conversion() {
+        assert!(format(PixelFormat::Rgba8, AlphaMode::Straight).is_rgba());
+        assert!(!format(PixelFormat::Rgba8, AlphaMode::Premultiplied).is_rgba());
+        assert!(!format(PixelFormat::Bgra8, AlphaMode::Straight).is_rgba());
+    }
+}
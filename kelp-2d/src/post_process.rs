@@ -0,0 +1,515 @@
+use crate::{
+    CanvasViewport, DitherMode, KelpError, KelpLutId, KelpMap, KelpPaletteId, KelpShaderId, PaletteCache, PostEffect,
+    PostEffectDesc, PostEffectKind,
+};
+use bytemuck::{Pod, Zeroable};
+use pollster::FutureExt;
+use std::{borrow::Cow, mem::size_of};
+use wgpu::util::DeviceExt;
+
+const POST_PUSH_CONSTANT: wgpu::PushConstantRange = wgpu::PushConstantRange {
+    stages: wgpu::ShaderStages::FRAGMENT,
+    range: 0..size_of::<PostParams>() as u32,
+};
+
+/// Number of intermediate textures needed so that an effect can read both its input and the previous pass
+const INTERMEDIATE_COUNT: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct PostParams {
+    params: [f32; 8],
+    source_size: [f32; 4],
+}
+
+unsafe impl Zeroable for PostParams {}
+unsafe impl Pod for PostParams {}
+
+/// Which texture is bound to the `Aux` slot of a pass
+#[derive(Debug, Clone, Copy)]
+enum PassAux {
+    None,
+    EffectInput,
+    Lut(KelpLutId),
+    Palette,
+}
+
+/// A single full screen draw, an effect may need several of these
+#[derive(Debug, Clone, Copy)]
+struct PostPass {
+    shader: KelpShaderId,
+    params: [f32; 8],
+    aux: PassAux,
+}
+
+#[derive(Debug)]
+struct BuiltinShaders {
+    blit: KelpShaderId,
+    blur: KelpShaderId,
+    bloom: KelpShaderId,
+    vignette: KelpShaderId,
+    color_grade: KelpShaderId,
+    scanlines: KelpShaderId,
+    chromatic_aberration: KelpShaderId,
+    lut: KelpShaderId,
+    quantize: KelpShaderId,
+}
+
+/// Owns the offscreen scene texture that replaces the surface as the default render target when a canvas or any
+/// post effects are in use, and the full screen passes that take it to the surface at the end of the frame.
+#[derive(Debug)]
+pub(crate) struct PostProcess {
+    vertex_shader: wgpu::ShaderModule,
+    bind_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    pipelines: KelpMap<KelpShaderId, wgpu::RenderPipeline>,
+    builtins: BuiltinShaders,
+    point_sampler: wgpu::Sampler,
+    linear_sampler: wgpu::Sampler,
+    effects: Vec<PostEffect>,
+    luts: KelpMap<KelpLutId, (u32, wgpu::Texture)>,
+    scene: Option<wgpu::Texture>,
+    intermediates: Vec<wgpu::Texture>,
+}
+
+impl PostProcess {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Glsl {
+                shader: Cow::Borrowed(include_str!("../shaders/glsl/target.vert")),
+                stage: wgpu::naga::ShaderStage::Vertex,
+                defines: Default::default(),
+            },
+        });
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let bind_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Process Bind Group Layout"),
+            entries: &[
+                texture_entry(0),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                texture_entry(3),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_layout],
+            push_constant_ranges: &[POST_PUSH_CONSTANT],
+        });
+
+        let mut pipelines = KelpMap::default();
+        let mut builtin = |source: &'static str| {
+            let shader = Self::create_fragment_shader(device, Cow::Borrowed(source));
+            let id = KelpShaderId(shader.global_id());
+            pipelines.insert(id, Self::create_pipeline(device, &pipeline_layout, &vertex_shader, &shader, format));
+            id
+        };
+        let builtins = BuiltinShaders {
+            blit: builtin(include_str!("../shaders/glsl/blit.frag")),
+            blur: builtin(include_str!("../shaders/glsl/blur.frag")),
+            bloom: builtin(include_str!("../shaders/glsl/bloom.frag")),
+            vignette: builtin(include_str!("../shaders/glsl/vignette.frag")),
+            color_grade: builtin(include_str!("../shaders/glsl/color_grade.frag")),
+            scanlines: builtin(include_str!("../shaders/glsl/scanlines.frag")),
+            chromatic_aberration: builtin(include_str!("../shaders/glsl/chromatic_aberration.frag")),
+            lut: builtin(include_str!("../shaders/glsl/lut.frag")),
+            quantize: builtin(include_str!("../shaders/glsl/quantize.frag")),
+        };
+
+        let point_sampler =
+            device.create_sampler(&wgpu::SamplerDescriptor { label: Some("Post Point Sampler"), ..Default::default() });
+        let linear_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Linear Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            vertex_shader,
+            bind_layout,
+            pipeline_layout,
+            format,
+            pipelines,
+            builtins,
+            point_sampler,
+            linear_sampler,
+            effects: Vec::new(),
+            luts: Default::default(),
+            scene: None,
+            intermediates: Vec::new(),
+        }
+    }
+
+    pub fn create_shader(&mut self, device: &wgpu::Device, source: &str) -> Result<KelpShaderId, KelpError> {
+        // Catch compilation errors here rather than letting wgpu panic on user code
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = Self::create_fragment_shader(device, Cow::Owned(source.to_owned()));
+        let pipeline = Self::create_pipeline(device, &self.pipeline_layout, &self.vertex_shader, &shader, self.format);
+        if let Some(error) = device.pop_error_scope().block_on() {
+            return Err(KelpError::ShaderError(error.to_string()));
+        }
+        let id = KelpShaderId(shader.global_id());
+        self.pipelines.insert(id, pipeline);
+        Ok(id)
+    }
+
+    pub fn create_lut(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: u32,
+        data: &[u8],
+    ) -> Result<KelpLutId, KelpError> {
+        if size < 2 {
+            return Err(KelpError::InvalidDataLength);
+        }
+        // The slices are laid out in a strip, which must fit in a single texture
+        let width = u64::from(size) * u64::from(size);
+        let max = device.limits().max_texture_dimension_2d;
+        if width > u64::from(max) {
+            return Err(KelpError::TextureTooLarge { width: size.saturating_mul(size), height: size, max });
+        }
+        if data.len() as u64 != width * u64::from(size) * 4 {
+            return Err(KelpError::InvalidDataLength);
+        }
+        // LUTs are authored in sRGB, so keep the values as they are and convert in the shader
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("LUT Texture"),
+                size: wgpu::Extent3d { width: width as u32, height: size, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            data,
+        );
+        let id = KelpLutId(texture.global_id());
+        self.luts.insert(id, (size, texture));
+        Ok(id)
+    }
+
+    /// Free a LUT, removing any effects that use it
+    pub fn remove_lut(&mut self, lut: KelpLutId) -> Result<(), KelpError> {
+        self.luts.swap_remove(&lut).ok_or(KelpError::InvalidLutId)?;
+        self.effects.retain(|effect| !matches!(effect, PostEffect::Lut { lut: used, .. } if *used == lut));
+        Ok(())
+    }
+
+    pub fn set_effects(&mut self, effects: &[PostEffect], palettes: &PaletteCache) -> Result<(), KelpError> {
+        // Check every effect is valid now, rather than failing on present
+        for effect in effects {
+            self.effect_passes(effect, palettes)?;
+        }
+        self.effects = effects.to_vec();
+        Ok(())
+    }
+
+    /// Find the LUT or shader an FFI description refers to, and read its parameters
+    pub fn effect_from_desc(&self, desc: &PostEffectDesc) -> Result<PostEffect, KelpError> {
+        let [a, b, c, ..] = desc.params;
+        let lut = || self.luts.keys().find(|lut| lut.0.inner() == desc.id).copied().ok_or(KelpError::InvalidLutId);
+        let shader = || {
+            let custom = |shader: &&KelpShaderId| shader.0.inner() == desc.id;
+            self.pipelines.keys().find(custom).copied().ok_or(KelpError::InvalidShaderId)
+        };
+        Ok(match desc.kind {
+            PostEffectKind::Blur => PostEffect::Blur { radius: a },
+            PostEffectKind::Bloom => PostEffect::Bloom { threshold: a, intensity: b, radius: c },
+            PostEffectKind::Vignette => PostEffect::Vignette { intensity: a, radius: b, softness: c },
+            PostEffectKind::ColorGrade => PostEffect::ColorGrade { brightness: a, contrast: b, saturation: c },
+            PostEffectKind::Scanlines => PostEffect::Scanlines { intensity: a, spacing: b },
+            PostEffectKind::ChromaticAberration => PostEffect::ChromaticAberration { offset: a },
+            PostEffectKind::Lut => PostEffect::Lut { lut: lut()?, intensity: a },
+            PostEffectKind::Quantize => PostEffect::Quantize {
+                palette: KelpPaletteId(u32::try_from(desc.id).map_err(|_| KelpError::InvalidPaletteId)?),
+                dither: match a as u8 {
+                    1 => DitherMode::Ordered,
+                    2 => DitherMode::Diffusion,
+                    _ => DitherMode::None,
+                },
+                dither_strength: b,
+            },
+            PostEffectKind::Custom => PostEffect::Custom { shader: shader()?, params: desc.params },
+        })
+    }
+
+    pub fn has_effects(&self) -> bool {
+        !self.effects.is_empty()
+    }
+
+    pub fn scene(&self) -> Option<&wgpu::Texture> {
+        self.scene.as_ref()
+    }
+
+    /// Create, resize or remove the scene texture
+    pub fn set_scene_size(&mut self, device: &wgpu::Device, size: Option<(u32, u32)>) {
+        let current = self.scene.as_ref().map(|scene| (scene.width(), scene.height()));
+        if current != size {
+            self.intermediates.clear();
+            self.scene = size.map(|(width, height)| self.create_target(device, "Scene Texture", width, height));
+        }
+    }
+
+    pub fn present(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        surface: &wgpu::Texture,
+        viewport: CanvasViewport,
+        bars: wgpu::Color,
+        palettes: &PaletteCache,
+    ) -> Result<(), KelpError> {
+        let Some(scene) = &self.scene else {
+            return Ok(());
+        };
+
+        if !self.effects.is_empty() && self.intermediates.is_empty() {
+            let (width, height) = (scene.width(), scene.height());
+            self.intermediates = (0..INTERMEDIATE_COUNT)
+                .map(|_| self.create_target(device, "Post Process Texture", width, height))
+                .collect();
+        }
+
+        let scene_view = scene.create_view(&Default::default());
+        let intermediate_views: Vec<_> =
+            self.intermediates.iter().map(|texture| texture.create_view(&Default::default())).collect();
+        let view = |index: Option<usize>| index.map_or(&scene_view, |i| &intermediate_views[i]);
+        let palette_view = palettes.create_view();
+        let source_size = {
+            let (width, height) = (scene.width() as f32, scene.height() as f32);
+            [width, height, 1.0 / width, 1.0 / height]
+        };
+
+        // Ping-pong between the intermediates, never writing to the scene so it can be loaded next frame
+        let mut source = None;
+        for effect in &self.effects {
+            let effect_input = source;
+            for pass in self.effect_passes(effect, palettes)? {
+                let target = (0..INTERMEDIATE_COUNT).find(|&i| Some(i) != source && Some(i) != effect_input).unwrap();
+                let lut_view;
+                let aux = match pass.aux {
+                    PassAux::None => view(source),
+                    PassAux::EffectInput => view(effect_input),
+                    PassAux::Lut(id) => {
+                        lut_view =
+                            self.luts.get(&id).ok_or(KelpError::InvalidLutId)?.1.create_view(&Default::default());
+                        &lut_view
+                    }
+                    PassAux::Palette => &palette_view,
+                };
+                let params = PostParams { params: pass.params, source_size };
+                let bind_group = self.create_bind_group(device, view(source), aux);
+                let mut wgpu_pass = Self::begin_pass(encoder, view(Some(target)), wgpu::Color::TRANSPARENT);
+                self.draw_pass(&mut wgpu_pass, pass.shader, &bind_group, &params)?;
+                source = Some(target);
+            }
+        }
+
+        // Finally scale the result onto the surface, before any imgui rendering
+        let surface_view = surface.create_view(&Default::default());
+        let params = PostParams { params: [0.0; 8], source_size };
+        let bind_group = self.create_bind_group(device, view(source), view(source));
+        let mut wgpu_pass = Self::begin_pass(encoder, &surface_view, bars);
+        // A minimised window has no area to present into
+        if viewport.width > 0.0 && viewport.height > 0.0 {
+            wgpu_pass.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height, 0.0, 1.0);
+            self.draw_pass(&mut wgpu_pass, self.builtins.blit, &bind_group, &params)?;
+        }
+
+        Ok(())
+    }
+
+    /* private */
+    fn effect_passes(&self, effect: &PostEffect, palettes: &PaletteCache) -> Result<Vec<PostPass>, KelpError> {
+        let pass = |shader, params: [f32; 4]| PostPass {
+            shader,
+            params: [params[0], params[1], params[2], params[3], 0.0, 0.0, 0.0, 0.0],
+            aux: PassAux::None,
+        };
+        let builtins = &self.builtins;
+        Ok(match *effect {
+            PostEffect::Blur { radius } => {
+                vec![pass(builtins.blur, [1.0, 0.0, radius, 0.0]), pass(builtins.blur, [0.0, 1.0, radius, 0.0])]
+            }
+            PostEffect::Bloom { threshold, intensity, radius } => vec![
+                pass(builtins.bloom, [0.0, threshold, radius, intensity]),
+                PostPass {
+                    aux: PassAux::EffectInput,
+                    ..pass(builtins.bloom, [1.0, threshold, radius, intensity])
+                },
+            ],
+            PostEffect::Vignette { intensity, radius, softness } => {
+                vec![pass(builtins.vignette, [intensity, radius, softness, 0.0])]
+            }
+            PostEffect::ColorGrade { brightness, contrast, saturation } => {
+                vec![pass(builtins.color_grade, [brightness, contrast, saturation, 0.0])]
+            }
+            PostEffect::Scanlines { intensity, spacing } => {
+                vec![pass(builtins.scanlines, [intensity, spacing, 0.0, 0.0])]
+            }
+            PostEffect::ChromaticAberration { offset } => {
+                vec![pass(builtins.chromatic_aberration, [offset, 0.0, 0.0, 0.0])]
+            }
+            PostEffect::Lut { lut, intensity } => {
+                let (size, _) = self.luts.get(&lut).ok_or(KelpError::InvalidLutId)?;
+                vec![PostPass {
+                    aux: PassAux::Lut(lut),
+                    ..pass(builtins.lut, [*size as f32, intensity, 0.0, 0.0])
+                }]
+            }
+            PostEffect::Quantize { palette, dither, dither_strength } => {
+                let params =
+                    [palette.0 as f32, palettes.color_count(palette)? as f32, dither as u8 as f32, dither_strength];
+                vec![PostPass { aux: PassAux::Palette, ..pass(builtins.quantize, params) }]
+            }
+            PostEffect::Custom { shader, params } => {
+                if !self.pipelines.contains_key(&shader) {
+                    return Err(KelpError::InvalidShaderId);
+                }
+                vec![PostPass { shader, params, aux: PassAux::None }]
+            }
+        })
+    }
+
+    fn draw_pass<'a>(
+        &'a self,
+        wgpu_pass: &mut wgpu::RenderPass<'a>,
+        shader: KelpShaderId,
+        bind_group: &'a wgpu::BindGroup,
+        params: &PostParams,
+    ) -> Result<(), KelpError> {
+        let pipeline = self.pipelines.get(&shader).ok_or(KelpError::InvalidShaderId)?;
+        wgpu_pass.set_pipeline(pipeline);
+        wgpu_pass.set_push_constants(wgpu::ShaderStages::FRAGMENT, 0, bytemuck::bytes_of(params));
+        wgpu_pass.set_bind_group(0, bind_group, &[]);
+        wgpu_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+
+    fn begin_pass<'a>(
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &'a wgpu::TextureView,
+        clear: wgpu::Color,
+    ) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Process Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        })
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        source: &wgpu::TextureView,
+        aux: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Post Process Bind Group"),
+            layout: &self.bind_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.point_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.linear_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(aux),
+                },
+            ],
+        })
+    }
+
+    fn create_target(&self, device: &wgpu::Device, label: &str, width: u32, height: u32) -> wgpu::Texture {
+        // Match the texture format with the surface, so we can reuse the sprite pipelines
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+    }
+
+    fn create_fragment_shader(device: &wgpu::Device, source: Cow<str>) -> wgpu::ShaderModule {
+        device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Glsl {
+                shader: source,
+                stage: wgpu::naga::ShaderStage::Fragment,
+                defines: Default::default(),
+            },
+        })
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        vertex_shader: &wgpu::ShaderModule,
+        fragment_shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post Process Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState { module: vertex_shader, entry_point: "main", buffers: &[] },
+            fragment: Some(wgpu::FragmentState {
+                module: fragment_shader,
+                entry_point: "main",
+                targets: &[Some(wgpu::ColorTargetState { blend: None, format, write_mask: wgpu::ColorWrites::ALL })],
+            }),
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview: None,
+        })
+    }
+}
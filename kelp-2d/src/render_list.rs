@@ -1,6 +1,6 @@
 use crate::{
-    BlendMode, Camera, InstanceBatch, InstanceData, InstanceGPU, Kelp, KelpColor, KelpError, KelpTargetId,
-    KelpTextureId,
+    BlendMode, Camera, ColorSpace, InstanceBatch, InstanceData, InstanceGPU, Kelp, KelpColor, KelpError, KelpTargetId,
+    KelpTextureId, TextInstances,
 };
 
 /// The data for a submitted render list
@@ -32,29 +32,155 @@ impl RenderList {
         blend_mode: BlendMode,
         instance_data: &[InstanceData],
     ) -> Result<Self, KelpError> {
+        self.extend_instances(kelp, texture, smooth, instance_data)?;
+        self.batches.push(InstanceBatch {
+            blend_mode,
+            instance_count: instance_data.len() as u32,
+            texture,
+        });
+        Ok(self)
+    }
+
+    /// Add text from `Kelp::text_instances`, with one batch for each glyph page it uses
+    pub fn add_text(mut self, kelp: &Kelp, text: &TextInstances, blend_mode: BlendMode) -> Result<Self, KelpError> {
+        for (page, instances) in &text.pages {
+            self = self.add_instances(kelp, *page, text.smooth, blend_mode, instances)?;
+        }
+        Ok(self)
+    }
+
+    /// Add instances to the last batch if it has the same blend mode and binds the same texture, otherwise start a new
+    /// one. Everything in the atlas shares a binding, but dedicated textures each have their own.
+    pub(crate) fn merge_instances(
+        &mut self,
+        kelp: &Kelp,
+        texture: KelpTextureId,
+        smooth: bool,
+        blend_mode: BlendMode,
+        instance_data: &[InstanceData],
+    ) -> Result<(), KelpError> {
+        self.extend_instances(kelp, texture, smooth, instance_data)?;
+        match self.batches.last_mut() {
+            Some(batch)
+                if batch.blend_mode == blend_mode
+                    && (batch.texture == texture || !(batch.texture.is_dedicated() || texture.is_dedicated())) =>
+            {
+                batch.instance_count += instance_data.len() as u32
+            }
+            _ => self.batches.push(InstanceBatch {
+                blend_mode,
+                instance_count: instance_data.len() as u32,
+                texture,
+            }),
+        }
+        Ok(())
+    }
+
+    /* private */
+    fn extend_instances(
+        &mut self,
+        kelp: &Kelp,
+        texture: KelpTextureId,
+        smooth: bool,
+        instance_data: &[InstanceData],
+    ) -> Result<(), KelpError> {
         // TODO: document the atlas source transform better lol
-        let tex_rect = kelp.texture_cache.borrow().get_texture(texture)?.rectangle;
-        self.batches.push(InstanceBatch { blend_mode, instance_count: instance_data.len() as u32 });
-        self.instances.extend(instance_data.iter().map(
-            |InstanceData { color, mode, source_trans, source_scale, world }| InstanceGPU {
+        let texture_cache = kelp.texture_cache.borrow();
+        let tex_alloc = texture_cache.get_texture(texture)?;
+        let tex_rect = tex_alloc.rectangle;
+        for palette in instance_data.iter().filter_map(|instance| instance.palette) {
+            kelp.palette_cache.color_count(palette)?;
+        }
+        self.instances.reserve(instance_data.len());
+        for InstanceData {
+            color,
+            mode,
+            source_trans,
+            source_scale,
+            world,
+            palette,
+            effect,
+            effect_colors,
+            region,
+            wrap,
+            mipmaps,
+        } in instance_data
+        {
+            // The source is relative to the region if there is one, otherwise to the whole texture
+            let (source_texture, source_rect, mip_levels, page_size, color_space) = match region {
+                Some(region) => {
+                    let (region_texture, rect) = texture_cache.get_region(*region)?;
+                    // Regions of other textures share the atlas binding, unless they have a texture of their own
+                    if region_texture != texture && (region_texture.is_dedicated() || texture.is_dedicated()) {
+                        return Err(KelpError::RegionTextureMismatch);
+                    }
+                    let region_alloc = texture_cache.get_texture(region_texture)?;
+                    let min =
+                        [region_alloc.rectangle.min.x as f32 + rect.x, region_alloc.rectangle.min.y as f32 + rect.y];
+                    (
+                        region_texture,
+                        [min[0], min[1], rect.width, rect.height],
+                        region_alloc.mip_levels,
+                        region_alloc.page_size,
+                        region_alloc.color_space,
+                    )
+                }
+                None => (
+                    texture,
+                    [tex_rect.min.x as f32, tex_rect.min.y as f32, tex_rect.width() as f32, tex_rect.height() as f32],
+                    tex_alloc.mip_levels,
+                    tex_alloc.page_size,
+                    tex_alloc.color_space,
+                ),
+            };
+            // Indexed textures are read from the index atlas, and linear ones through the non-sRGB view of the atlas
+            let texture_kind = match (source_texture.is_indexed(), color_space) {
+                (true, _) => 2.0,
+                (false, ColorSpace::Linear) => 1.0,
+                (false, ColorSpace::Srgb) => 0.0,
+            };
+            // UVs are relative to the atlas layer, or the whole of a dedicated texture
+            let (page_width, page_height) = (page_size.0 as f32, page_size.1 as f32);
+            // Trilinear filtering is a third sampling option, only taken by smooth instances of mipmapped textures
+            let filter = match (smooth, *mipmaps && mip_levels > 1) {
+                (true, true) => 2.0,
+                (true, false) => 1.0,
+                (false, _) => 0.0,
+            };
+            self.instances.push(InstanceGPU {
                 color: [color.x, color.y, color.z, color.w],
                 mode: (*mode).into(),
-                layer_smooth: [texture.layer as f32, smooth.into()],
-                // TODO: DO NOT hardcode the atlas size yo
+                sampling: [
+                    source_texture.array_layer() as f32,
+                    filter,
+                    palette.map_or(-1.0, |p| p.0 as f32),
+                    *wrap as u8 as f32,
+                ],
+                effect: [effect.x, effect.y, effect.z, effect.w],
+                effect_color_1: effect_colors[0].into(),
+                effect_color_2: effect_colors[1].into(),
+                // Wrapping keeps within the allocation or region, in UVs as min then max
+                bounds: [
+                    source_rect[0] / page_width,
+                    source_rect[1] / page_height,
+                    (source_rect[0] + source_rect[2]) / page_width,
+                    (source_rect[1] + source_rect[3]) / page_height,
+                ],
                 // TODO: ohh could some of this go in the shader with push constants instead???
                 source_trans: [
-                    (tex_rect.min.x as f32 + source_trans.x) / 2048.0,
-                    (tex_rect.min.y as f32 + source_trans.y) / 2048.0,
+                    (source_rect[0] + source_trans.x) / page_width,
+                    (source_rect[1] + source_trans.y) / page_height,
                 ],
                 source_scale: [
-                    source_scale.x * tex_rect.width() as f32 / 2048.0,
-                    source_scale.y * tex_rect.height() as f32 / 2048.0,
+                    source_scale.x * source_rect[2] / page_width,
+                    source_scale.y * source_rect[3] / page_height,
                 ],
                 world_col_1: [world.x.x, world.x.y],
                 world_col_2: [world.y.x, world.y.y],
                 world_trans: [world.z.x, world.z.y],
-            },
-        ));
-        Ok(self)
+                texture_info: [(mip_levels - 1) as f32, texture_kind],
+            });
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,152 @@
+use crate::{
+    BlendMode, Camera, InstanceData, Kelp, KelpColor, KelpError, KelpRect, KelpRegionId, KelpTargetId, KelpTextureId,
+    NineSlice, RenderList, SpriteFlip, SpriteFrame, TextInstances, Transform2D,
+};
+
+/// Builds a `RenderList` one sprite at a time, only starting a new batch when the blend mode changes
+pub struct SpriteBatch<'a> {
+    kelp: &'a Kelp,
+    list: RenderList,
+    blend_mode: BlendMode,
+    smooth: bool,
+}
+
+impl<'a> SpriteBatch<'a> {
+    pub fn new(kelp: &'a Kelp, target: Option<KelpTargetId>, camera: &Camera, clear: Option<&KelpColor>) -> Self {
+        Self {
+            kelp,
+            list: RenderList::new(target, camera, clear),
+            blend_mode: BlendMode::ALPHA,
+            smooth: false,
+        }
+    }
+
+    /// Set the blend mode of the sprites drawn after this
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    /// Set whether the sprites drawn after this use linear filtering
+    pub fn set_smooth(&mut self, smooth: bool) {
+        self.smooth = smooth;
+    }
+
+    /// Draw part of a texture, or all of it without a `source_rect`, with its `origin` at `position`.
+    /// The origin is in pixels from the top left of the source, and is also what the sprite rotates and scales around.
+    /// A source rectangle outside the texture is rejected, as it would draw neighbouring textures in the atlas.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        texture: KelpTextureId,
+        position: mint::Vector2<f32>,
+        rotation: f32,
+        scale: mint::Vector2<f32>,
+        origin: mint::Vector2<f32>,
+        color: mint::Vector4<f32>,
+        flip: SpriteFlip,
+        source_rect: Option<KelpRect>,
+    ) -> Result<(), KelpError> {
+        let texture_rect = self.kelp.texture_cache.borrow().get_texture(texture)?.rectangle;
+        let (texture_width, texture_height) = (texture_rect.width() as f32, texture_rect.height() as f32);
+        let source = source_rect.unwrap_or(KelpRect { x: 0.0, y: 0.0, width: texture_width, height: texture_height });
+        let in_bounds = source.x >= 0.0
+            && source.y >= 0.0
+            && source.width > 0.0
+            && source.height > 0.0
+            && source.x + source.width <= texture_width
+            && source.y + source.height <= texture_height;
+        if !in_bounds {
+            return Err(KelpError::RegionOutOfBounds);
+        }
+
+        // Flipping mirrors the source within the same quad, by starting from its far edge with a negative scale
+        let (mut source_trans, mut source_scale) =
+            ([source.x, source.y], [source.width / texture_width, source.height / texture_height]);
+        if flip.horizontal() {
+            source_trans[0] += source.width;
+            source_scale[0] = -source_scale[0];
+        }
+        if flip.vertical() {
+            source_trans[1] += source.height;
+            source_scale[1] = -source_scale[1];
+        }
+
+        let transform = Transform2D {
+            x: position.x,
+            y: position.y,
+            rotation,
+            scale_x: scale.x,
+            scale_y: scale.y,
+            origin_x: origin.x,
+            origin_y: origin.y,
+            ..Default::default()
+        };
+        let instance = InstanceData {
+            color,
+            source_trans: source_trans.into(),
+            source_scale: source_scale.into(),
+            world: transform.world(source.width, source.height),
+            ..Default::default()
+        };
+        self.list.merge_instances(self.kelp, texture, self.smooth, self.blend_mode, &[instance])
+    }
+
+    /// Draw a region of a texture from `Kelp::create_region`, with the same options as `draw`
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_region(
+        &mut self,
+        region: KelpRegionId,
+        position: mint::Vector2<f32>,
+        rotation: f32,
+        scale: mint::Vector2<f32>,
+        origin: mint::Vector2<f32>,
+        color: mint::Vector4<f32>,
+        flip: SpriteFlip,
+    ) -> Result<(), KelpError> {
+        let (texture, rect) = self.kelp.region(region)?;
+        self.draw(texture, position, rotation, scale, origin, color, flip, Some(rect))
+    }
+
+    /// Draw a sprite sheet frame with its pivot at `position`, accounting for any trimming
+    pub fn draw_frame(
+        &mut self,
+        frame: &SpriteFrame,
+        position: mint::Vector2<f32>,
+        rotation: f32,
+        scale: mint::Vector2<f32>,
+        color: mint::Vector4<f32>,
+        flip: SpriteFlip,
+    ) -> Result<(), KelpError> {
+        let origin = frame.origin(flip.horizontal(), flip.vertical());
+        self.draw_region(frame.region, position, rotation, scale, origin, color, flip)
+    }
+
+    /// Draw a nine-slice stretched or tiled over `rect`
+    pub fn draw_nine_slice(
+        &mut self,
+        nine_slice: &NineSlice,
+        rect: KelpRect,
+        color: mint::Vector4<f32>,
+    ) -> Result<(), KelpError> {
+        let (texture, instances) = nine_slice.instances(self.kelp, rect, color)?;
+        self.list.merge_instances(self.kelp, texture, self.smooth, self.blend_mode, &instances)
+    }
+
+    /// Draw instances built by hand, which are batched like any other sprites
+    pub fn draw_instances(&mut self, texture: KelpTextureId, instance_data: &[InstanceData]) -> Result<(), KelpError> {
+        self.list.merge_instances(self.kelp, texture, self.smooth, self.blend_mode, instance_data)
+    }
+
+    /// Draw text from `Kelp::text_instances` or `Kelp::layout_text`
+    pub fn draw_text(&mut self, text: &TextInstances) -> Result<(), KelpError> {
+        for (page, instances) in &text.pages {
+            self.list.merge_instances(self.kelp, *page, text.smooth, self.blend_mode, instances)?;
+        }
+        Ok(())
+    }
+
+    /// Finish drawing, ready to pass the list to `Kelp::render_list`
+    pub fn finish(self) -> RenderList {
+        self.list
+    }
+}
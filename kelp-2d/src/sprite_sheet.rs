@@ -0,0 +1,436 @@
+use crate::{
+    AnimationClip, Kelp, KelpError, KelpMap, KelpRect, KelpRegionId, KelpTextureId, LoopMode, SheetGrid, SpriteFrame,
+    SpriteSheet, DEFAULT_FRAME_DURATION,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct JsonSheet {
+    frames: JsonFrames,
+    #[serde(default)]
+    meta: JsonMeta,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct JsonMeta {
+    #[serde(default)]
+    frame_tags: Vec<JsonTag>,
+}
+
+#[derive(Deserialize)]
+struct JsonTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+    /// How many times to play, where absent or "0" means forever
+    repeat: Option<String>,
+}
+
+// TexturePacker and Aseprite both export frames either keyed by name, or as an array with a filename each
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonFrames {
+    Hash(KelpMap<String, JsonFrame>),
+    Array(Vec<JsonFrame>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonFrame {
+    #[serde(default)]
+    filename: String,
+    frame: JsonRect,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: Option<JsonRect>,
+    source_size: Option<JsonSize>,
+    pivot: Option<JsonPoint>,
+    /// In milliseconds, only exported by Aseprite
+    duration: Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct JsonRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+#[derive(Deserialize)]
+struct JsonSize {
+    w: f32,
+    h: f32,
+}
+
+#[derive(Deserialize)]
+struct JsonPoint {
+    x: f32,
+    y: f32,
+}
+
+/// A frame read from a sheet, before a region is created for it
+#[derive(Debug)]
+struct SheetFrame {
+    name: String,
+    rect: KelpRect,
+    offset: [f32; 2],
+    source_size: [f32; 2],
+    pivot: [f32; 2],
+    duration: Option<f32>,
+}
+
+impl SpriteSheet {
+    /// Find a frame by its name
+    pub fn frame(&self, name: &str) -> Option<&SpriteFrame> {
+        self.frames.iter().find(|frame| frame.name == name)
+    }
+
+    pub(crate) fn from_grid(
+        kelp: &mut Kelp,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        grid: &SheetGrid,
+    ) -> Result<Self, KelpError> {
+        let frames = grid_frames(width, height, grid)?;
+        Self::create(kelp, width, height, data, frames, &[])
+    }
+
+    pub(crate) fn from_json(
+        kelp: &mut Kelp,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        json: &str,
+    ) -> Result<Self, KelpError> {
+        let (frames, tags) = json_frames(json)?;
+        Self::create(kelp, width, height, data, frames, &tags)
+    }
+
+    /// Find an animation by its tag name
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.iter().find(|clip| clip.name == name)
+    }
+
+    /* private */
+    /// Create the texture and a region for each frame, checking the frames fit first so that the texture is only
+    /// created for a sheet that can be made from it
+    fn create(
+        kelp: &mut Kelp,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        frames: Vec<SheetFrame>,
+        tags: &[JsonTag],
+    ) -> Result<Self, KelpError> {
+        check_frames_fit(&frames, width, height)?;
+        let texture = kelp.create_texture_with_data(width, height, data)?;
+        let sheet = Self::build(texture, frames, tags, |rect| kelp.create_region(texture, rect));
+        if sheet.is_err() {
+            kelp.remove_texture(texture)?;
+        }
+        sheet
+    }
+
+    fn build(
+        texture: KelpTextureId,
+        frames: Vec<SheetFrame>,
+        tags: &[JsonTag],
+        mut create_region: impl FnMut(KelpRect) -> Result<KelpRegionId, KelpError>,
+    ) -> Result<Self, KelpError> {
+        let frames = frames
+            .into_iter()
+            .map(|frame| {
+                Ok(SpriteFrame {
+                    name: frame.name,
+                    region: create_region(frame.rect)?,
+                    offset: frame.offset.into(),
+                    size: [frame.rect.width, frame.rect.height].into(),
+                    source_size: frame.source_size.into(),
+                    pivot: frame.pivot.into(),
+                    duration: frame.duration,
+                })
+            })
+            .collect::<Result<_, KelpError>>()?;
+        let mut sprite_sheet = Self { texture, frames, clips: Vec::new() };
+        sprite_sheet.clips = tags.iter().map(|tag| sprite_sheet.tag_clip(tag)).collect::<Result<_, _>>()?;
+        Ok(sprite_sheet)
+    }
+
+    fn tag_clip(&self, tag: &JsonTag) -> Result<AnimationClip, KelpError> {
+        // Aseprite counts a ping-pong pass in either direction as one repeat, which the animator does too
+        let repeat = tag.repeat.as_deref().and_then(|repeat| repeat.parse().ok()).unwrap_or(0);
+        let once = repeat == 1;
+        let (reverse, loop_mode) = match tag.direction.as_str() {
+            "pingpong" => (false, LoopMode::PingPong),
+            "pingpong_reverse" => (true, LoopMode::PingPong),
+            "reverse" => (true, if once { LoopMode::Once } else { LoopMode::Loop }),
+            _ => (false, if once { LoopMode::Once } else { LoopMode::Loop }),
+        };
+        let mut clip =
+            AnimationClip::from_sheet(self, &tag.name, tag.from..tag.to + 1, DEFAULT_FRAME_DURATION, loop_mode)?;
+        clip.repeat = repeat;
+        if reverse {
+            clip.frames.reverse();
+        }
+        Ok(clip)
+    }
+}
+
+/// Check every frame is within the sheet, as a region would be
+fn check_frames_fit(frames: &[SheetFrame], width: u32, height: u32) -> Result<(), KelpError> {
+    let outside = |rect: &KelpRect| {
+        rect.x < 0.0
+            || rect.y < 0.0
+            || rect.width <= 0.0
+            || rect.height <= 0.0
+            || rect.x + rect.width > width as f32
+            || rect.y + rect.height > height as f32
+    };
+    match frames.iter().any(|frame| outside(&frame.rect)) {
+        true => Err(KelpError::RegionOutOfBounds),
+        false => Ok(()),
+    }
+}
+
+/// Slice a sheet into frames on a grid, numbered along each row from the top left
+fn grid_frames(width: u32, height: u32, grid: &SheetGrid) -> Result<Vec<SheetFrame>, KelpError> {
+    if grid.frame_width == 0 || grid.frame_height == 0 {
+        return Err(KelpError::SheetError("grid frames must have a size".to_string()));
+    }
+    let fit = |size: u32, frame: u32| (size.saturating_sub(2 * grid.margin) + grid.spacing) / (frame + grid.spacing);
+    let columns = fit(width, grid.frame_width);
+    let rows = fit(height, grid.frame_height);
+    let count = grid.count.map_or(columns * rows, |count| count.min(columns * rows));
+    let size = [grid.frame_width as f32, grid.frame_height as f32];
+    let frames = (0..count)
+        .map(|index| SheetFrame {
+            name: index.to_string(),
+            rect: KelpRect {
+                x: (grid.margin + (index % columns) * (grid.frame_width + grid.spacing)) as f32,
+                y: (grid.margin + (index / columns) * (grid.frame_height + grid.spacing)) as f32,
+                width: size[0],
+                height: size[1],
+            },
+            offset: [0.0, 0.0],
+            source_size: size,
+            pivot: grid.pivot.into(),
+            duration: None,
+        })
+        .collect();
+    Ok(frames)
+}
+
+/// Read the frames and animation tags of a TexturePacker or Aseprite JSON export
+fn json_frames(json: &str) -> Result<(Vec<SheetFrame>, Vec<JsonTag>), KelpError> {
+    let sheet: JsonSheet = serde_json::from_str(json).map_err(|err| KelpError::SheetError(err.to_string()))?;
+    let frames: Vec<(String, JsonFrame)> = match sheet.frames {
+        JsonFrames::Hash(frames) => frames.into_iter().collect(),
+        JsonFrames::Array(frames) => frames.into_iter().map(|frame| (frame.filename.clone(), frame)).collect(),
+    };
+    if let Some((name, _)) = frames.iter().find(|(_, frame)| frame.rotated) {
+        return Err(KelpError::SheetError(format!("frame {name} is rotated, which is not supported")));
+    }
+    if let Some(tag) = sheet.meta.frame_tags.iter().find(|tag| tag.from > tag.to || tag.to >= frames.len()) {
+        return Err(KelpError::SheetError(format!("tag {} refers to missing frames", tag.name)));
+    }
+    let frames = frames
+        .into_iter()
+        .map(|(name, frame)| {
+            let rect = KelpRect {
+                x: frame.frame.x,
+                y: frame.frame.y,
+                width: frame.frame.w,
+                height: frame.frame.h,
+            };
+            let source_size = frame.source_size.map_or([rect.width, rect.height], |size| [size.w, size.h]);
+            SheetFrame {
+                name,
+                rect,
+                offset: frame.sprite_source_size.map_or([0.0, 0.0], |trim| [trim.x, trim.y]),
+                source_size,
+                // Pivots are exported relative to the untrimmed size
+                pivot: frame.pivot.map_or([0.0, 0.0], |pivot| [pivot.x * source_size[0], pivot.y * source_size[1]]),
+                duration: frame.duration.map(|duration| duration / 1000.0),
+            }
+        })
+        .collect();
+    Ok((frames, sheet.meta.frame_tags))
+}
+
+impl SpriteFrame {
+    /// The origin to draw the trimmed region with, so that the pivot lands on the drawn position
+    pub fn origin(&self, flip_horizontal: bool, flip_vertical: bool) -> mint::Vector2<f32> {
+        // A flipped region is mirrored within its own quad, so the pivot is measured from its far edge
+        let origin = |pivot: f32, offset: f32, size: f32, flip: bool| {
+            if flip {
+                offset + size - pivot
+            } else {
+                pivot - offset
+            }
+        };
+        mint::Vector2 {
+            x: origin(self.pivot.x, self.offset.x, self.size.x, flip_horizontal),
+            y: origin(self.pivot.y, self.offset.y, self.size.y, flip_vertical),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRID: SheetGrid = SheetGrid {
+        frame_width: 16,
+        frame_height: 16,
+        margin: 2,
+        spacing: 4,
+        count: None,
+        pivot: mint::Vector2 { x: 8.0, y: 16.0 },
+    };
+
+    const TEXTURE_PACKER: &str = r#"{
+        "frames": {
+            "idle.png": {
+                "frame": { "x": 0, "y": 0, "w": 16, "h": 16 },
+                "rotated": false,
+                "trimmed": false,
+                "spriteSourceSize": { "x": 0, "y": 0, "w": 16, "h": 16 },
+                "sourceSize": { "w": 16, "h": 16 }
+            },
+            "walk.png": {
+                "frame": { "x": 16, "y": 4, "w": 10, "h": 12 },
+                "rotated": false,
+                "trimmed": true,
+                "spriteSourceSize": { "x": 3, "y": 1, "w": 10, "h": 12 },
+                "sourceSize": { "w": 16, "h": 16 },
+                "pivot": { "x": 0.5, "y": 1.0 }
+            }
+        },
+        "meta": { "app": "https://www.codeandweb.com/texturepacker", "size": { "w": 32, "h": 16 } }
+    }"#;
+
+    const ASEPRITE: &str = r#"{
+        "frames": [
+            { "filename": "hero 0", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "duration": 100 },
+            { "filename": "hero 1", "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "duration": 150 },
+            { "filename": "hero 2", "frame": { "x": 16, "y": 0, "w": 8, "h": 8 }, "duration": 200 }
+        ],
+        "meta": {
+            "frameTags": [
+                { "name": "run", "from": 0, "to": 2, "direction": "forward" },
+                { "name": "hit", "from": 1, "to": 2, "direction": "reverse", "repeat": "1" },
+                { "name": "bounce", "from": 0, "to": 2, "direction": "pingpong", "repeat": "3" }
+            ]
+        }
+    }"#;
+
+    /// Build a sheet with regions numbered in the order they are created
+    fn build(frames: Vec<SheetFrame>, tags: &[JsonTag]) -> SpriteSheet {
+        let mut next = 0;
+        let texture = KelpTextureId { layer: 0, index: 0 };
+        SpriteSheet::build(texture, frames, tags, |_| {
+            next += 1;
+            Ok(KelpRegionId(next - 1))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn slices_grids_around_margins_and_spacing() {
+        let frames = grid_frames(70, 40, &GRID).unwrap();
+        // Three columns fit across, with the spare pixels on the right
+        assert_eq!(frames.len(), 6);
+        let frame = &frames[4];
+        assert_eq!(frame.name, "4");
+        assert_eq!((frame.rect.x, frame.rect.y, frame.rect.width, frame.rect.height), (22.0, 22.0, 16.0, 16.0));
+        assert_eq!(frame.pivot, [8.0, 16.0]);
+        assert!(check_frames_fit(&frames, 70, 40).is_ok());
+    }
+
+    #[test]
+    fn stops_grids_at_the_frame_count() {
+        let grid = SheetGrid { count: Some(4), ..GRID };
+        assert_eq!(grid_frames(70, 40, &grid).unwrap().len(), 4);
+        let grid = SheetGrid { count: Some(100), ..GRID };
+        assert_eq!(grid_frames(70, 40, &grid).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn handles_grids_that_do_not_fit() {
+        assert!(grid_frames(10, 10, &GRID).unwrap().is_empty());
+        assert!(grid_frames(70, 40, &SheetGrid { frame_width: 0, ..GRID }).is_err());
+    }
+
+    #[test]
+    fn reads_texture_packer_trimming_and_pivots() {
+        let (frames, tags) = json_frames(TEXTURE_PACKER).unwrap();
+        assert!(tags.is_empty());
+        let sheet = build(frames, &tags);
+        let walk = sheet.frame("walk.png").unwrap();
+        assert_eq!(walk.region, KelpRegionId(1));
+        assert_eq!((walk.offset.x, walk.offset.y), (3.0, 1.0));
+        assert_eq!((walk.size.x, walk.size.y), (10.0, 12.0));
+        assert_eq!((walk.source_size.x, walk.source_size.y), (16.0, 16.0));
+        assert_eq!((walk.pivot.x, walk.pivot.y), (8.0, 16.0));
+        assert_eq!(walk.duration, None);
+        // The pivot lands on the drawn position however the trimmed frame is flipped
+        let origin = walk.origin(false, false);
+        assert_eq!((origin.x, origin.y), (5.0, 15.0));
+        let origin = walk.origin(true, true);
+        assert_eq!((origin.x, origin.y), (5.0, -3.0));
+        let idle = sheet.frame("idle.png").unwrap();
+        assert_eq!((idle.pivot.x, idle.pivot.y), (0.0, 0.0));
+    }
+
+    #[test]
+    fn reads_aseprite_durations_and_tags() {
+        let (frames, tags) = json_frames(ASEPRITE).unwrap();
+        let sheet = build(frames, &tags);
+        assert_eq!(sheet.frames[1].name, "hero 1");
+        assert_eq!(sheet.frames[1].duration, Some(0.15));
+
+        let run = sheet.clip("run").unwrap();
+        assert_eq!((run.loop_mode, run.repeat), (LoopMode::Loop, 0));
+        assert_eq!(run.duration(), 0.45);
+
+        let hit = sheet.clip("hit").unwrap();
+        assert_eq!(hit.loop_mode, LoopMode::Once);
+        let names: Vec<_> = hit.frames.iter().map(|frame| frame.frame.name.as_str()).collect();
+        assert_eq!(names, ["hero 2", "hero 1"]);
+
+        let bounce = sheet.clip("bounce").unwrap();
+        assert_eq!((bounce.loop_mode, bounce.repeat), (LoopMode::PingPong, 3));
+    }
+
+    #[test]
+    fn rejects_unsupported_json() {
+        assert!(json_frames("not json").is_err());
+        let rotated = TEXTURE_PACKER.replacen(r#""rotated": false"#, r#""rotated": true"#, 1);
+        assert!(json_frames(&rotated).is_err());
+        let missing = ASEPRITE.replace(r#""from": 1, "to": 2"#, r#""from": 1, "to": 3"#);
+        assert!(json_frames(&missing).is_err());
+    }
+
+    #[test]
+    fn checks_frames_fit_before_the_texture_is_created() {
+        let (frames, _) = json_frames(TEXTURE_PACKER).unwrap();
+        assert!(check_frames_fit(&frames, 32, 16).is_ok());
+        assert!(matches!(check_frames_fit(&frames, 24, 16), Err(KelpError::RegionOutOfBounds)));
+    }
+
+    #[test]
+    fn fails_to_build_when_a_region_cannot_be_created() {
+        let (frames, _) = json_frames(ASEPRITE).unwrap();
+        let texture = KelpTextureId { layer: 0, index: 0 };
+        let sheet = SpriteSheet::build(texture, frames, &[], |rect| match rect.x {
+            x if x > 0.0 => Err(KelpError::RegionOutOfBounds),
+            _ => Ok(KelpRegionId(0)),
+        });
+        assert!(sheet.is_err());
+    }
+}
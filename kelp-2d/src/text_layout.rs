@@ -0,0 +1,360 @@
+use crate::{TextAlign, TextLayout, TextSpan};
+
+/// What the layout needs to know about a font, at any size in pixels
+pub(crate) trait LayoutFont {
+    fn glyph_index(&self, character: char) -> u16;
+    fn advance(&self, glyph: u16, size: f32) -> f32;
+    fn kern(&self, left: u16, right: u16, size: f32) -> f32;
+    fn line_metrics(&self, size: f32) -> fontdue::LineMetrics;
+}
+
+impl LayoutFont for fontdue::Font {
+    fn glyph_index(&self, character: char) -> u16 {
+        self.lookup_glyph_index(character)
+    }
+
+    fn advance(&self, glyph: u16, size: f32) -> f32 {
+        self.metrics_indexed(glyph, size).advance_width
+    }
+
+    fn kern(&self, left: u16, right: u16, size: f32) -> f32 {
+        self.horizontal_kern_indexed(left, right, size).unwrap_or(0.0)
+    }
+
+    fn line_metrics(&self, size: f32) -> fontdue::LineMetrics {
+        self.horizontal_line_metrics(size).unwrap_or(fontdue::LineMetrics {
+            ascent: size,
+            descent: 0.0,
+            line_gap: 0.0,
+            new_line_size: size,
+        })
+    }
+}
+
+/// A glyph placed on its baseline, relative to the top left of the text
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PlacedGlyph {
+    pub(crate) glyph: u16,
+    pub(crate) size: f32,
+    pub(crate) color: mint::Vector4<f32>,
+    pub(crate) x: f32,
+    pub(crate) baseline: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WordKind {
+    Word,
+    Space,
+    Newline,
+}
+
+/// A run of glyphs that is never split across lines, with glyph positions relative to its start
+#[derive(Debug)]
+struct Word {
+    kind: WordKind,
+    glyphs: Vec<(f32, PlacedGlyph)>,
+    width: f32,
+    size: f32,
+    /// Kerning against the glyph before the word, only applied when it stays on the same line
+    kern: f32,
+}
+
+#[derive(Debug, Default)]
+struct Line {
+    // Each glyph also stores how many spaces come before it on the line, for justifying
+    glyphs: Vec<(f32, usize, PlacedGlyph)>,
+    width: f32,
+    spaces: usize,
+    size: f32,
+    ends_paragraph: bool,
+}
+
+/// Lay out spans of text from a top left of zero, returning the glyphs and measured width and height
+pub(crate) fn layout_text(
+    font: &impl LayoutFont,
+    spans: &[TextSpan],
+    layout: &TextLayout,
+) -> (Vec<PlacedGlyph>, f32, f32) {
+    let lines = break_lines(split_words(font, spans), layout.max_width);
+    let widest = lines.iter().fold(0.0_f32, |widest, line| widest.max(line.width));
+    let box_width = layout.max_width.unwrap_or(widest);
+
+    let mut glyphs = Vec::new();
+    let mut top = 0.0;
+    let mut width = 0.0_f32;
+    for line in &lines {
+        let metrics = font.line_metrics(line.size);
+        let baseline = top + metrics.ascent;
+        let free = box_width - line.width;
+        let (offset, space_extra) = match layout.align {
+            TextAlign::Left => (0.0, 0.0),
+            TextAlign::Center => (free / 2.0, 0.0),
+            TextAlign::Right => (free, 0.0),
+            // The last line of a paragraph stays left aligned, as does a line with nowhere to add space
+            TextAlign::Justify if line.ends_paragraph || line.spaces == 0 => (0.0, 0.0),
+            TextAlign::Justify => (0.0, free.max(0.0) / line.spaces as f32),
+        };
+        width = width.max(line.width + space_extra * line.spaces as f32);
+        glyphs.extend(line.glyphs.iter().map(|&(x, spaces, glyph)| PlacedGlyph {
+            x: x + offset + spaces as f32 * space_extra,
+            baseline,
+            ..glyph
+        }));
+        top += metrics.new_line_size * layout.line_height;
+    }
+    // The last line only needs to fit its glyphs, not the gap to a following line
+    if let Some(line) = lines.last() {
+        let metrics = font.line_metrics(line.size);
+        top += (metrics.ascent - metrics.descent) - metrics.new_line_size * layout.line_height;
+    }
+    (glyphs, width, top)
+}
+
+fn split_words(font: &impl LayoutFont, spans: &[TextSpan]) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut previous: Option<(u16, f32)> = None;
+    for span in spans {
+        for character in span.text.chars() {
+            let kind = match character {
+                '\n' => WordKind::Newline,
+                '\r' => continue,
+                character if character.is_whitespace() => WordKind::Space,
+                _ => WordKind::Word,
+            };
+            let glyph = font.glyph_index(character);
+            let placed = PlacedGlyph {
+                glyph,
+                size: span.size,
+                color: span.color,
+                x: 0.0,
+                baseline: 0.0,
+            };
+            let kern = match previous {
+                Some((left, size)) if size == span.size => font.kern(left, glyph, span.size),
+                _ => 0.0,
+            };
+            // Nothing kerns against a line break
+            previous = (kind != WordKind::Newline).then_some((glyph, span.size));
+            match words.last_mut() {
+                // Runs of word and space characters are grouped, but every newline stands alone
+                Some(word) if word.kind == kind && kind != WordKind::Newline => {
+                    word.glyphs.push((word.width + kern, placed));
+                    word.width += kern + font.advance(glyph, span.size);
+                    word.size = word.size.max(span.size);
+                }
+                _ => words.push(Word {
+                    kind,
+                    glyphs: vec![(0.0, placed)],
+                    width: if kind == WordKind::Newline {
+                        0.0
+                    } else {
+                        font.advance(glyph, span.size)
+                    },
+                    size: span.size,
+                    kern,
+                }),
+            }
+        }
+    }
+    words
+}
+
+fn break_lines(words: Vec<Word>, max_width: Option<f32>) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut line = Line::default();
+    // Spaces are only placed once a word follows them, so wrapped lines never start or end with space
+    let mut pending_space: Option<Word> = None;
+    for word in words {
+        match word.kind {
+            WordKind::Newline => {
+                line.size = line.size.max(word.size);
+                line.ends_paragraph = true;
+                lines.push(std::mem::take(&mut line));
+                pending_space = None;
+            }
+            WordKind::Space => pending_space = Some(word),
+            WordKind::Word => {
+                let space_width = pending_space.as_ref().map_or(0.0, |space| space.width);
+                let overflows = max_width.is_some_and(|max| line.width + space_width + word.width > max);
+                if overflows && !line.glyphs.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                    pending_space = None;
+                }
+                // A word at the start of a wrapped line has nothing before it to kern against
+                let kern = if line.glyphs.is_empty() && pending_space.is_none() {
+                    0.0
+                } else {
+                    word.kern
+                };
+                if let Some(space) = pending_space.take() {
+                    line.spaces += 1;
+                    line.width += space.kern + space.width;
+                    line.size = line.size.max(space.size);
+                }
+                line.width += kern;
+                let start = line.width;
+                let spaces = line.spaces;
+                line.glyphs.extend(word.glyphs.into_iter().map(|(x, glyph)| (start + x, spaces, glyph)));
+                line.width += word.width;
+                line.size = line.size.max(word.size);
+            }
+        }
+    }
+    line.ends_paragraph = true;
+    lines.push(line);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every glyph is half the size wide, with a few kerning pairs around 'A', 'V' and spaces
+    struct FakeFont;
+
+    impl LayoutFont for FakeFont {
+        fn glyph_index(&self, character: char) -> u16 {
+            character as u16
+        }
+
+        fn advance(&self, _glyph: u16, size: f32) -> f32 {
+            size / 2.0
+        }
+
+        fn kern(&self, left: u16, right: u16, size: f32) -> f32 {
+            match (left as u8 as char, right as u8 as char) {
+                ('A', 'V') => -size / 10.0,
+                ('A', ' ') | (' ', 'V') => -size / 20.0,
+                _ => 0.0,
+            }
+        }
+
+        fn line_metrics(&self, size: f32) -> fontdue::LineMetrics {
+            fontdue::LineMetrics {
+                ascent: size * 0.8,
+                descent: -size * 0.2,
+                line_gap: 0.0,
+                new_line_size: size,
+            }
+        }
+    }
+
+    const WHITE: mint::Vector4<f32> = mint::Vector4 { x: 1.0, y: 1.0, z: 1.0, w: 1.0 };
+
+    fn span(text: &str) -> TextSpan<'_> {
+        TextSpan { text, color: WHITE, size: 20.0 }
+    }
+
+    fn layout(text: &str, max_width: Option<f32>, align: TextAlign) -> (Vec<PlacedGlyph>, f32, f32) {
+        layout_text(&FakeFont, &[span(text)], &TextLayout { max_width, align, line_height: 1.0 })
+    }
+
+    fn positions(glyphs: &[PlacedGlyph]) -> Vec<(char, f32, f32)> {
+        glyphs.iter().map(|glyph| (glyph.glyph as u8 as char, glyph.x, glyph.baseline)).collect()
+    }
+
+    #[test]
+    fn measures_a_single_line() {
+        let (glyphs, width, height) = layout("ab", None, TextAlign::Left);
+        assert_eq!(positions(&glyphs), [('a', 0.0, 16.0), ('b', 10.0, 16.0)]);
+        assert_eq!((width, height), (20.0, 20.0));
+    }
+
+    #[test]
+    fn kerns_within_words() {
+        let (glyphs, width, _) = layout("AVA", None, TextAlign::Left);
+        assert_eq!(positions(&glyphs), [('A', 0.0, 16.0), ('V', 8.0, 16.0), ('A', 18.0, 16.0)]);
+        assert_eq!(width, 28.0);
+    }
+
+    #[test]
+    fn kerns_across_spaces() {
+        // 'A' to the space and the space to 'V' each kern by a pixel
+        let (glyphs, width, _) = layout("A V", None, TextAlign::Left);
+        assert_eq!(positions(&glyphs), [('A', 0.0, 16.0), ('V', 18.0, 16.0)]);
+        assert_eq!(width, 28.0);
+    }
+
+    #[test]
+    fn drops_kerning_at_the_start_of_a_wrapped_line() {
+        let (glyphs, _, _) = layout("A V", Some(15.0), TextAlign::Left);
+        assert_eq!(positions(&glyphs), [('A', 0.0, 16.0), ('V', 0.0, 36.0)]);
+    }
+
+    #[test]
+    fn wraps_between_words() {
+        let (glyphs, width, height) = layout("aa bb cc", Some(50.0), TextAlign::Left);
+        let lines: Vec<_> = positions(&glyphs).into_iter().map(|(_, x, baseline)| (x, baseline)).collect();
+        assert_eq!(lines, [(0.0, 16.0), (10.0, 16.0), (30.0, 16.0), (40.0, 16.0), (0.0, 36.0), (10.0, 36.0)]);
+        assert_eq!((width, height), (50.0, 40.0));
+    }
+
+    #[test]
+    fn overflows_with_words_wider_than_the_line() {
+        let (glyphs, width, _) = layout("a bbbbbb", Some(30.0), TextAlign::Left);
+        assert_eq!(glyphs[1].x, 0.0);
+        assert_eq!(glyphs[1].baseline, 36.0);
+        assert_eq!(width, 60.0);
+    }
+
+    #[test]
+    fn keeps_newlines_and_empty_lines() {
+        let (glyphs, _, height) = layout("a\n\nb", None, TextAlign::Left);
+        assert_eq!(positions(&glyphs), [('a', 0.0, 16.0), ('b', 0.0, 56.0)]);
+        assert_eq!(height, 60.0);
+    }
+
+    #[test]
+    fn aligns_lines_within_the_maximum_width() {
+        let first_x = |align| {
+            let (glyphs, _, _) = layout("aa bb cc", Some(60.0), align);
+            (glyphs[0].x, glyphs[4].x)
+        };
+        assert_eq!(first_x(TextAlign::Left), (0.0, 0.0));
+        assert_eq!(first_x(TextAlign::Center), (5.0, 20.0));
+        assert_eq!(first_x(TextAlign::Right), (10.0, 40.0));
+    }
+
+    #[test]
+    fn aligns_to_the_widest_line_without_a_maximum_width() {
+        let (glyphs, width, _) = layout("aaaa\nbb", None, TextAlign::Right);
+        assert_eq!(glyphs[4].x, 20.0);
+        assert_eq!(width, 40.0);
+    }
+
+    #[test]
+    fn justifies_all_but_the_last_line_of_a_paragraph() {
+        let (glyphs, width, _) = layout("aa bb cc dd", Some(60.0), TextAlign::Justify);
+        // The first line spreads its one space by the 10 free pixels, the last stays left aligned
+        assert_eq!(glyphs[2].x, 40.0);
+        assert_eq!((glyphs[4].x, glyphs[6].x), (0.0, 30.0));
+        assert_eq!(width, 60.0);
+    }
+
+    #[test]
+    fn justify_leaves_lines_without_spaces_alone() {
+        let (glyphs, width, _) = layout("aaaa", Some(60.0), TextAlign::Justify);
+        assert_eq!(glyphs[3].x, 30.0);
+        assert_eq!(width, 40.0);
+    }
+
+    #[test]
+    fn spans_keep_their_own_size_and_colour() {
+        let red = mint::Vector4 { x: 1.0, y: 0.0, z: 0.0, w: 1.0 };
+        let spans = [span("A"), TextSpan { text: "V", color: red, size: 40.0 }];
+        let (glyphs, width, height) = layout_text(&FakeFont, &spans, &TextLayout::default());
+        // No kerning between sizes, and both sit on the baseline of the larger span
+        assert_eq!(positions(&glyphs), [('A', 0.0, 32.0), ('V', 10.0, 32.0)]);
+        assert_eq!((glyphs[0].size, glyphs[1].size), (20.0, 40.0));
+        assert_eq!(glyphs[1].color, red);
+        assert_eq!((width, height), (30.0, 40.0));
+    }
+
+    #[test]
+    fn spaces_line_height_by_the_multiplier() {
+        let layout = TextLayout { line_height: 1.5, ..Default::default() };
+        let (glyphs, _, height) = layout_text(&FakeFont, &[span("a\nb")], &layout);
+        assert_eq!(glyphs[1].baseline, 46.0);
+        assert_eq!(height, 50.0);
+    }
+}
@@ -1,41 +1,265 @@
-use crate::{KelpError, KelpMap, KelpTargetId, KelpTextureId};
+use crate::{
+    AtlasCompaction, ColorSpace, KelpError, KelpMap, KelpRect, KelpRegionId, KelpTargetId, KelpTextureId, UploadFormat,
+};
+
+/// Mip levels of the atlas texture array, including the full size level
+pub(crate) const ATLAS_MIP_LEVELS: u32 = 4;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) struct TextureAllocation {
     pub(crate) id: KelpTextureId,
+    /// The allocator's id for the outer rectangle, which changes when the atlas is compacted, or `None` for a
+    /// dedicated texture
+    pub(crate) alloc_id: Option<guillotiere::AllocId>,
+    /// The usable area, inside the padding
     pub(crate) rectangle: guillotiere::Rectangle,
+    /// The whole area reserved in the atlas, including the padding
+    pub(crate) outer: guillotiere::Rectangle,
+    /// Whether uploads repeat their edge pixels into the padding, rather than leaving it transparent
+    pub(crate) extrude: bool,
+    /// Mip levels generated on upload, including the full size level
+    pub(crate) mip_levels: u32,
+    /// Size of the texture the allocation is in, which UVs are relative to
+    pub(crate) page_size: (u32, u32),
+    /// Whether the texture holds sRGB colours or linear values, which decides how it is sampled and mipmapped
+    pub(crate) color_space: ColorSpace,
+}
+
+/// A texture too large for the atlas, with a bind group like the atlas one to draw it with
+pub(crate) struct DedicatedTexture {
+    pub(crate) texture: wgpu::Texture,
+    pub(crate) bind_group: wgpu::BindGroup,
+}
+
+/// Everything besides its data that a deduplicated texture must match, as the same pixels uploaded in another format
+/// or with other atlas padding or mip levels make a different texture
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ContentKey {
+    size: (u32, u32),
+    format: UploadFormat,
+    color_space: ColorSpace,
+    padding: u32,
+    extrude: bool,
+    mip_levels: u32,
+}
+
+/// A deduplicated texture, with a second hash of the data it was created with to compare against. This takes the
+/// place of keeping a copy of the data, which would cost as much memory as deduplication saves.
+struct SharedContent {
+    texture_id: KelpTextureId,
+    key: ContentKey,
+    check: u64,
 }
 
-// TODO: move the samplers outta here now?
 pub(crate) struct TextureCache {
     allocators: Vec<guillotiere::AtlasAllocator>,
+    /// Space in the single channel atlas that indexed textures are kept in
+    index_allocator: guillotiere::AtlasAllocator,
     texture_cache: KelpMap<KelpTextureId, TextureAllocation>,
     target_cache: KelpMap<KelpTargetId, wgpu::Texture>,
-    point_sampler: wgpu::Sampler,
-    linear_sampler: wgpu::Sampler,
+    dedicated: KelpMap<KelpTextureId, DedicatedTexture>,
+    /// Textures created so far, numbering their ids so that freed ones are not reused
+    texture_count: u32,
+    /// Whether textures created with data share an existing texture with identical content
+    deduplicate: bool,
+    hash_state: ahash::RandomState,
+    /// Seeded independently of `hash_state`, so content with the same hash is very unlikely to share this one too
+    check_state: ahash::RandomState,
+    /// Deduplicated textures by the hash of their content, until they are freed or their content is replaced
+    contents: KelpMap<u64, Vec<SharedContent>>,
+    /// References to each deduplicated texture, which is only freed once all of them have been removed
+    references: KelpMap<KelpTextureId, u32>,
+    atlas_size: (u32, u32),
+    regions: Vec<(KelpTextureId, KelpRect)>,
+    padding: u32,
+    extrude: bool,
+    mip_levels: u32,
 }
 
 impl TextureCache {
-    pub fn new(texture_array: &wgpu::Texture, point_sampler: wgpu::Sampler, linear_sampler: wgpu::Sampler) -> Self {
+    pub fn new(texture_array: &wgpu::Texture) -> Self {
         let alloc_size = guillotiere::Size::new(texture_array.width() as i32, texture_array.height() as i32);
         let layers = texture_array.depth_or_array_layers() as usize;
         Self {
             allocators: vec![guillotiere::AtlasAllocator::new(alloc_size); layers],
+            index_allocator: guillotiere::AtlasAllocator::new(alloc_size),
             texture_cache: Default::default(),
             target_cache: Default::default(),
-            point_sampler,
-            linear_sampler,
+            dedicated: Default::default(),
+            texture_count: 0,
+            deduplicate: false,
+            hash_state: Default::default(),
+            check_state: Default::default(),
+            contents: Default::default(),
+            references: Default::default(),
+            atlas_size: (texture_array.width(), texture_array.height()),
+            regions: Vec::new(),
+            padding: 1,
+            extrude: true,
+            mip_levels: 1,
         }
     }
 
-    pub fn new_texture_alloc(&mut self, width: u32, height: u32) -> KelpTextureId {
-        // TODO: handle extending the array! and error for failing to do so
-        let allocation = self.allocate_texture(width as i32, height as i32).unwrap();
+    /// Set the padding around textures allocated after this
+    pub fn set_padding(&mut self, padding: u32, extrude: bool) {
+        self.padding = padding;
+        self.extrude = extrude;
+    }
+
+    /// Set how many mip levels textures allocated after this have, including the full size level
+    pub fn set_mip_levels(&mut self, mip_levels: u32) {
+        self.mip_levels = mip_levels.clamp(1, ATLAS_MIP_LEVELS);
+    }
+
+    /// Allocate space in the atlas, or `None` if there is no room for a texture of this size
+    pub fn new_texture_alloc(&mut self, width: u32, height: u32) -> Option<KelpTextureId> {
+        // TODO: handle extending the array!
+        let allocation = self.allocate_texture(width as i32, height as i32, self.mip_levels)?;
         let id = allocation.id;
         self.texture_cache.insert(id, allocation);
+        Some(id)
+    }
+
+    /// Allocate space like `new_texture_alloc` for a texture only ever written at full size, such as a glyph page, so
+    /// without any mip levels
+    pub fn new_page_alloc(&mut self, width: u32, height: u32) -> Option<KelpTextureId> {
+        let allocation = self.allocate_texture(width as i32, height as i32, 1)?;
+        let id = allocation.id;
+        self.texture_cache.insert(id, allocation);
+        Some(id)
+    }
+
+    /// Allocate space in the index atlas for an indexed texture, or `None` if there is no room for one of this size.
+    /// Indexed textures are only drawn with point sampling, so are padded without extruding and have no mip levels.
+    pub fn new_indexed_alloc(&mut self, width: u32, height: u32) -> Option<KelpTextureId> {
+        let (padding, size_with_padding) = self.padded_size(width as i32, height as i32, 1);
+        let guillotiere::Allocation { id: alloc_id, rectangle: outer } =
+            self.index_allocator.allocate(size_with_padding)?;
+        let min = outer.min + guillotiere::size2(padding, padding);
+        let id = KelpTextureId {
+            layer: KelpTextureId::INDEXED_LAYER,
+            index: self.next_index(),
+        };
+        let allocation = TextureAllocation {
+            id,
+            alloc_id: Some(alloc_id),
+            rectangle: guillotiere::Rectangle {
+                min,
+                max: min + guillotiere::size2(width as i32, height as i32),
+            },
+            outer,
+            extrude: false,
+            mip_levels: 1,
+            page_size: self.atlas_size,
+            color_space: ColorSpace::Linear,
+        };
+        self.texture_cache.insert(id, allocation);
+        Some(id)
+    }
+
+    /// Whether a texture of this size fits in an empty atlas layer, with its padding
+    pub fn fits_layer(&self, width: u32, height: u32) -> bool {
+        let (_, size) = self.padded_size(width as i32, height as i32, self.mip_levels);
+        size.width as u32 <= self.atlas_size.0 && size.height as u32 <= self.atlas_size.1
+    }
+
+    /// Add a texture of its own, for one that does not fit in the atlas
+    pub fn insert_dedicated(&mut self, texture: wgpu::Texture, bind_group: wgpu::BindGroup) -> KelpTextureId {
+        let id = KelpTextureId {
+            layer: KelpTextureId::DEDICATED_LAYER,
+            index: self.next_index(),
+        };
+        let size = guillotiere::point2(texture.width() as i32, texture.height() as i32);
+        let rectangle = guillotiere::Rectangle { min: guillotiere::point2(0, 0), max: size };
+        self.texture_cache.insert(
+            id,
+            TextureAllocation {
+                id,
+                alloc_id: None,
+                rectangle,
+                outer: rectangle,
+                extrude: false,
+                mip_levels: 1,
+                page_size: (texture.width(), texture.height()),
+                color_space: ColorSpace::Srgb,
+            },
+        );
+        self.dedicated.insert(id, DedicatedTexture { texture, bind_group });
         id
     }
 
+    /// Free a texture's space in the atlas, or its dedicated texture. Deduplicated textures are only freed when the
+    /// last of their references is removed.
+    pub fn remove_texture(&mut self, texture_id: KelpTextureId) -> Result<(), KelpError> {
+        let allocation = self.get_texture(texture_id)?;
+        if let Some(references) = self.references.get_mut(&texture_id) {
+            *references -= 1;
+            if *references > 0 {
+                return Ok(());
+            }
+            self.references.swap_remove(&texture_id);
+            self.forget_content(texture_id);
+        }
+        self.texture_cache.swap_remove(&texture_id);
+        match allocation.alloc_id {
+            Some(alloc_id) if texture_id.is_indexed() => self.index_allocator.deallocate(alloc_id),
+            Some(alloc_id) => self.allocators[texture_id.layer as usize].deallocate(alloc_id),
+            None => _ = self.dedicated.swap_remove(&texture_id),
+        }
+        Ok(())
+    }
+
+    /// Set whether textures created with data after this share existing textures with identical content
+    pub fn set_deduplicate(&mut self, deduplicate: bool) {
+        self.deduplicate = deduplicate;
+    }
+
+    /// What identifies a texture created with data besides the data itself, or `None` if deduplication is off
+    pub fn content_key(&self, size: (u32, u32), format: UploadFormat, color_space: ColorSpace) -> Option<ContentKey> {
+        self.deduplicate.then_some(ContentKey {
+            size,
+            format,
+            color_space,
+            padding: self.padding,
+            extrude: self.extrude,
+            mip_levels: self.mip_levels,
+        })
+    }
+
+    /// Add a reference to an existing texture with the same content, if there is one. Textures with the same hash must
+    /// also match a second independent hash, so sharing the wrong one needs two 64 bit collisions at once.
+    pub fn share_texture(&mut self, data: &[u8], key: ContentKey) -> Option<KelpTextureId> {
+        let hash = self.hash_state.hash_one((data, key));
+        let check = self.check_state.hash_one(data);
+        let shared = self.contents.get(&hash)?.iter().find(|shared| shared.key == key && shared.check == check)?;
+        let texture_id = shared.texture_id;
+        *self.references.entry(texture_id).or_default() += 1;
+        Some(texture_id)
+    }
+
+    /// Keep the hashes of a newly created texture's content, so later textures with the same content share it
+    pub fn insert_content(&mut self, data: &[u8], key: ContentKey, texture_id: KelpTextureId) {
+        let hash = self.hash_state.hash_one((data, key));
+        let check = self.check_state.hash_one(data);
+        let shared = SharedContent { texture_id, key, check };
+        self.contents.entry(hash).or_default().push(shared);
+        self.references.insert(texture_id, 1);
+    }
+
+    /// Stop sharing a texture with new textures, such as when its content is replaced
+    pub fn forget_content(&mut self, texture_id: KelpTextureId) {
+        if self.references.contains_key(&texture_id) {
+            self.contents.retain(|_, shared| {
+                shared.retain(|shared| shared.texture_id != texture_id);
+                !shared.is_empty()
+            });
+        }
+    }
+
+    pub fn get_dedicated(&self, texture_id: KelpTextureId) -> Result<&DedicatedTexture, KelpError> {
+        self.dedicated.get(&texture_id).ok_or(KelpError::InvalidTextureId)
+    }
+
     pub fn insert_target(&mut self, texture: wgpu::Texture) -> KelpTargetId {
         let id = KelpTargetId(texture.global_id());
         self.target_cache.insert(id, texture);
@@ -46,13 +270,117 @@ impl TextureCache {
         self.texture_cache.get(&texture_id).map(Clone::clone).ok_or(KelpError::InvalidTextureId)
     }
 
+    pub fn set_color_space(&mut self, texture_id: KelpTextureId, color_space: ColorSpace) -> Result<(), KelpError> {
+        let allocation = self.texture_cache.get_mut(&texture_id).ok_or(KelpError::InvalidTextureId)?;
+        allocation.color_space = color_space;
+        Ok(())
+    }
+
+    pub fn insert_region(&mut self, texture_id: KelpTextureId, rect: KelpRect) -> Result<KelpRegionId, KelpError> {
+        let rectangle = self.get_texture(texture_id)?.rectangle;
+        let in_bounds = rect.x >= 0.0
+            && rect.y >= 0.0
+            && rect.width > 0.0
+            && rect.height > 0.0
+            && rect.x + rect.width <= rectangle.width() as f32
+            && rect.y + rect.height <= rectangle.height() as f32;
+        if !in_bounds {
+            return Err(KelpError::RegionOutOfBounds);
+        }
+        self.regions.push((texture_id, rect));
+        Ok(KelpRegionId(self.regions.len() as u32 - 1))
+    }
+
+    pub fn get_region(&self, region_id: KelpRegionId) -> Result<(KelpTextureId, KelpRect), KelpError> {
+        self.regions.get(region_id.0 as usize).copied().ok_or(KelpError::InvalidRegionId)
+    }
+
     pub fn get_target(&self, target_id: KelpTargetId) -> Result<&wgpu::Texture, KelpError> {
         self.target_cache.get(&target_id).ok_or(KelpError::InvalidTextureId)
     }
 
+    /// Repack the allocations in each atlas layer to merge their free space, keeping texture ids the same. Returns
+    /// each allocation that moved, before and after, with a report of the space reclaimed. The index atlas is left as it
+    /// is.
+    pub fn compact(&mut self) -> (Vec<(TextureAllocation, TextureAllocation)>, AtlasCompaction) {
+        let largest_free_before = self.largest_free_area();
+        let ids: KelpMap<(u32, guillotiere::AllocId), KelpTextureId> = self
+            .texture_cache
+            .values()
+            .filter_map(|allocation| Some(((allocation.id.layer, allocation.alloc_id?), allocation.id)))
+            .collect();
+        let mut moves = Vec::new();
+        let mut skipped = 0;
+        for (layer, allocator) in self.allocators.iter_mut().enumerate() {
+            let layer = layer as u32;
+            // Rearrange a copy, leaving the layer as it was if anything no longer fits. The rearranged positions ignore
+            // mip alignment, so the layer is also left as it was if any texture would lose levels by moving.
+            let mut rearranged = allocator.clone();
+            let changes = rearranged.rearrange();
+            let keeps_levels = changes.changes.iter().all(|change| {
+                ids.get(&(layer, change.old.id)).and_then(|id| self.texture_cache.get(id)).is_none_or(|allocation| {
+                    aligned_mip_levels(change.new.rectangle.min, allocation.mip_levels) == allocation.mip_levels
+                })
+            });
+            if !changes.failures.is_empty() || !keeps_levels {
+                skipped += ids.keys().filter(|(id_layer, _)| *id_layer == layer).count() as u32;
+                continue;
+            }
+            *allocator = rearranged;
+            for guillotiere::Change { old, new } in changes.changes {
+                let Some(allocation) = ids.get(&(layer, old.id)).and_then(|id| self.texture_cache.get_mut(id)) else {
+                    continue;
+                };
+                let previous = *allocation;
+                let offset = new.rectangle.min - old.rectangle.min;
+                allocation.alloc_id = Some(new.id);
+                allocation.outer = new.rectangle;
+                allocation.rectangle = previous.rectangle.translate(offset);
+                if new.rectangle.min != old.rectangle.min {
+                    moves.push((previous, *allocation));
+                }
+            }
+        }
+        let report = AtlasCompaction {
+            moved: moves.len() as u32,
+            skipped,
+            largest_free_before,
+            largest_free_after: self.largest_free_area(),
+        };
+        (moves, report)
+    }
+
     /* private */
-    fn allocate_texture(&mut self, width: i32, height: i32) -> Option<TextureAllocation> {
-        let size_with_padding = guillotiere::Size::new(width + 1, height + 1);
+    fn next_index(&mut self) -> u32 {
+        self.texture_count += 1;
+        self.texture_count - 1
+    }
+
+    /// Area of the largest free rectangle in any atlas layer
+    fn largest_free_area(&self) -> u32 {
+        let mut largest = 0;
+        for allocator in &self.allocators {
+            allocator.for_each_free_rectangle(|rectangle| largest = largest.max(rectangle.area() as u32));
+        }
+        largest
+    }
+
+    /// The padding around an allocation of this size, and the whole area it reserves
+    fn padded_size(&self, width: i32, height: i32, mip_levels: u32) -> (i32, guillotiere::Size) {
+        // Mipmapped textures start and end on the texel boundaries of their smallest level, with at least a texel of
+        // that level as padding, so that no level filters in pixels from a neighbouring allocation
+        let align = 1 << (mip_levels - 1);
+        let padding = if mip_levels > 1 {
+            self.padding.max(align as u32)
+        } else {
+            self.padding
+        } as i32;
+        let round = |size: i32| (size + 2 * padding + align - 1) / align * align;
+        (padding, guillotiere::Size::new(round(width), round(height)))
+    }
+
+    fn allocate_texture(&mut self, width: i32, height: i32, mip_levels: u32) -> Option<TextureAllocation> {
+        let (padding, size_with_padding) = self.padded_size(width, height, mip_levels);
         let mut allocation: Option<guillotiere::Allocation> = None;
         let mut layer = 0;
         for (i, allocator) in self.allocators.iter_mut().enumerate() {
@@ -63,12 +391,220 @@ impl TextureCache {
             }
         }
 
-        if let Some(guillotiere::Allocation { id, mut rectangle }) = allocation {
-            rectangle.max.x -= 1;
-            rectangle.max.y -= 1;
-            Some(TextureAllocation { id: KelpTextureId { layer, alloc_id: id }, rectangle })
+        if let Some(guillotiere::Allocation { id, rectangle: outer }) = allocation {
+            let min = outer.min + guillotiere::size2(padding, padding);
+            // Earlier allocations of other sizes can leave this one off the boundaries, limiting the levels it gets
+            let mip_levels = aligned_mip_levels(outer.min, mip_levels);
+            Some(TextureAllocation {
+                id: KelpTextureId { layer, index: self.next_index() },
+                alloc_id: Some(id),
+                rectangle: guillotiere::Rectangle { min, max: min + guillotiere::size2(width, height) },
+                outer,
+                extrude: self.extrude,
+                mip_levels,
+                page_size: self.atlas_size,
+                color_space: ColorSpace::Srgb,
+            })
         } else {
             None
         }
     }
 }
+
+/// Mip levels an allocation starting at `min` can have, up to `mip_levels`, as each level needs it to start on a texel
+/// boundary of that level
+fn aligned_mip_levels(min: guillotiere::Point, mip_levels: u32) -> u32 {
+    (1..mip_levels).take_while(|level| (min.x | min.y) % (1 << level) == 0).count() as u32 + 1
+}
+
+/// Place rectangles left to right in rows, starting new layers of `bounds` as each fills up, with every position
+/// aligned. Returns the position and layer of each, and the size and layer count that holds them all.
+pub(crate) fn pack_rows(
+    sizes: &[(u32, u32)],
+    (max_width, max_height): (u32, u32),
+    align: u32,
+) -> (Vec<(guillotiere::Point, u32)>, wgpu::Extent3d) {
+    let (mut x, mut y, mut layer, mut row_height) = (0, 0, 0, 0);
+    let (mut width, mut height) = (0, 0);
+    let mut positions = Vec::with_capacity(sizes.len());
+    for &(rect_width, rect_height) in sizes {
+        let (rect_width, rect_height) = (rect_width.next_multiple_of(align), rect_height.next_multiple_of(align));
+        if x > 0 && x + rect_width > max_width {
+            (x, y, row_height) = (0, y + row_height, 0);
+        }
+        if y > 0 && y + rect_height > max_height {
+            (x, y, row_height, layer) = (0, 0, 0, layer + 1);
+        }
+        positions.push((guillotiere::point2(x as i32, y as i32), layer));
+        x += rect_width;
+        row_height = row_height.max(rect_height);
+        (width, height) = (width.max(x), height.max(y + row_height));
+    }
+    (positions, wgpu::Extent3d { width, height, depth_or_array_layers: layer + 1 })
+}
+
+/// Place RGBA pixel data at `offset` in a larger area, either surrounded by copies of its edge pixels or transparent
+pub(crate) fn pad_pixels(
+    data: &[u8],
+    (width, height): (u32, u32),
+    (outer_width, outer_height): (u32, u32),
+    offset: (u32, u32),
+    extrude: bool,
+) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let (offset_x, offset_y) = (offset.0 as usize, offset.1 as usize);
+    let outer_width = outer_width as usize;
+    let mut padded = vec![0; outer_width * outer_height as usize * 4];
+    for (y, padded_row) in padded.chunks_exact_mut(outer_width * 4).enumerate() {
+        let inside = (offset_y..offset_y + height).contains(&y);
+        if !inside && !extrude {
+            continue;
+        }
+        let row = y.saturating_sub(offset_y).min(height - 1) * width * 4;
+        let row = &data[row..row + width * 4];
+        for (x, pixel) in padded_row.chunks_exact_mut(4).enumerate() {
+            if extrude || (offset_x..offset_x + width).contains(&x) {
+                let x = x.saturating_sub(offset_x).min(width - 1) * 4;
+                pixel.copy_from_slice(&row[x..x + 4]);
+            }
+        }
+    }
+    padded
+}
+
+/// Halve RGBA pixel data, averaging colours in linear space and weighted by alpha. sRGB colours are converted to
+/// linear and back, while linear values are averaged as they are. Odd sizes round down like GPU mip levels, leaving out
+/// the last row or column, and sizes of 1 stay 1.
+pub(crate) fn downsample(data: &[u8], (width, height): (u32, u32), color_space: ColorSpace) -> Vec<u8> {
+    let srgb = color_space == ColorSpace::Srgb;
+    let to_linear: Vec<f32> = (0..=255)
+        .map(|value| {
+            let value = value as f32 / 255.0;
+            if !srgb {
+                value
+            } else if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        })
+        .collect();
+    let to_srgb = |value: f32| {
+        let value = if !srgb {
+            value
+        } else if value <= 0.0031308 {
+            value * 12.92
+        } else {
+            1.055 * value.powf(1.0 / 2.4) - 0.055
+        };
+        (value * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+    let (width, height) = (width as usize, height as usize);
+    let (half_width, half_height) = ((width / 2).max(1), (height / 2).max(1));
+    let mut half = Vec::with_capacity(half_width * half_height * 4);
+    for y in (0..half_height).map(|y| y * 2) {
+        for x in (0..half_width).map(|x| x * 2) {
+            let mut sum = [0.0_f32; 4];
+            let (right, below) = ((x + 1).min(width - 1), (y + 1).min(height - 1));
+            for (px, py) in [(x, y), (right, y), (x, below), (right, below)] {
+                let pixel = &data[(py * width + px) * 4..][..4];
+                let alpha = pixel[3] as f32 / 255.0;
+                for channel in 0..3 {
+                    sum[channel] += to_linear[pixel[channel] as usize] * alpha;
+                }
+                sum[3] += alpha;
+            }
+            let color = |channel: usize| {
+                if sum[3] > 0.0 {
+                    to_srgb(sum[channel] / sum[3])
+                } else {
+                    0
+                }
+            };
+            half.extend_from_slice(&[color(0), color(1), color(2), (sum[3] / 4.0 * 255.0).round() as u8]);
+        }
+    }
+    half
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixels(data: &[u8]) -> Vec<[u8; 4]> {
+        data.chunks_exact(4).map(|pixel| pixel.try_into().unwrap()).collect()
+    }
+
+    #[test]
+    fn pads_with_transparent_pixels() {
+        let data = [1, 2, 3, 255, 4, 5, 6, 255];
+        let padded = pad_pixels(&data, (2, 1), (4, 3), (1, 1), false);
+        let clear = [0, 0, 0, 0];
+        #[rustfmt::skip]
+        let expected = [
+            clear, clear, clear, clear,
+            clear, [1, 2, 3, 255], [4, 5, 6, 255], clear,
+            clear, clear, clear, clear,
+        ];
+        assert_eq!(pixels(&padded), expected);
+    }
+
+    #[test]
+    fn extrudes_edge_pixels_into_padding() {
+        let (a, b) = ([1, 2, 3, 255], [4, 5, 6, 128]);
+        let padded = pad_pixels(&[a, b].concat(), (2, 1), (4, 3), (1, 1), true);
+        assert_eq!(pixels(&padded), [[a, a, b, b]; 3].concat());
+    }
+
+    #[test]
+    fn downsamples_weighted_by_alpha() {
+        let white = [255, 255, 255, 255];
+        let clear_red = [255, 0, 0, 0];
+        let data = [white, clear_red, white, clear_red].concat();
+        // The transparent pixels do not tint the average, but do halve its alpha
+        assert_eq!(pixels(&downsample(&data, (2, 2), ColorSpace::Srgb)), [[255, 255, 255, 128]]);
+        let clear = [clear_red; 4].concat();
+        assert_eq!(pixels(&downsample(&clear, (2, 2), ColorSpace::Srgb)), [[0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn downsamples_srgb_in_linear_space() {
+        let data = [[0, 0, 0, 255], [255, 255, 255, 255]].repeat(2).concat();
+        let srgb = pixels(&downsample(&data, (2, 2), ColorSpace::Srgb))[0];
+        let linear = pixels(&downsample(&data, (2, 2), ColorSpace::Linear))[0];
+        // Half of linear white is brighter once encoded as sRGB
+        assert_eq!(srgb, [188, 188, 188, 255]);
+        assert_eq!(linear, [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn downsamples_odd_sizes_like_mip_levels() {
+        let data: Vec<u8> = (0..15).flat_map(|index| [index * 10, 0, 0, 255]).collect();
+        let half = pixels(&downsample(&data, (5, 3), ColorSpace::Linear));
+        // Rounded down to 2x1, from the first two rows and four columns
+        assert_eq!(half, [[30, 0, 0, 255], [50, 0, 0, 255]]);
+        let single = pixels(&downsample(&data[..12], (3, 1), ColorSpace::Linear));
+        assert_eq!(single, [[5, 0, 0, 255]]);
+        let one = pixels(&downsample(&[7, 8, 9, 255], (1, 1), ColorSpace::Linear));
+        assert_eq!(one, [[7, 8, 9, 255]]);
+    }
+
+    #[test]
+    fn packs_rows_into_layers() {
+        let sizes = [(6, 4), (8, 8), (8, 4), (16, 2), (4, 4)];
+        let (positions, size) = pack_rows(&sizes, (16, 16), 8);
+        let at = |x, y, layer| (guillotiere::point2(x, y), layer);
+        assert_eq!(positions, [at(0, 0, 0), at(8, 0, 0), at(0, 8, 0), at(0, 0, 1), at(0, 8, 1)]);
+        assert_eq!((size.width, size.height, size.depth_or_array_layers), (16, 16, 2));
+        let (positions, size) = pack_rows(&[(3, 5), (2, 2)], (64, 64), 1);
+        assert_eq!(positions, [at(0, 0, 0), at(3, 0, 0)]);
+        assert_eq!((size.width, size.height, size.depth_or_array_layers), (5, 5, 1));
+    }
+
+    #[test]
+    fn limits_mip_levels_to_the_alignment() {
+        assert_eq!(aligned_mip_levels(guillotiere::point2(8, 16), 4), 4);
+        assert_eq!(aligned_mip_levels(guillotiere::point2(4, 16), 4), 3);
+        assert_eq!(aligned_mip_levels(guillotiere::point2(8, 3), 4), 1);
+    }
+}
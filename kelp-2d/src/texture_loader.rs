@@ -0,0 +1,180 @@
+use crate::{decode_image, DecodedImage, KelpError, KelpLoadId, KelpMap, KelpTextureId, LoadProgress};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+/// Most worker threads to decode images on, fewer if the machine has fewer cores
+const MAX_WORKERS: usize = 4;
+
+pub(crate) enum LoadSource {
+    Path(PathBuf),
+    Memory(Vec<u8>),
+}
+
+type LoadJob = (KelpLoadId, LoadSource);
+type LoadResult = (KelpLoadId, Result<DecodedImage, KelpError>);
+
+enum LoadState {
+    Loading,
+    Ready(KelpTextureId),
+    Failed(KelpError),
+}
+
+/// Decodes images on worker threads, keeping the results until `Kelp` uploads them at the start of a frame
+pub(crate) struct TextureLoader {
+    /// Started with the first load, and dropped with the loader to stop the workers. Stays `None` if no worker
+    /// thread could be started, in which case images are decoded on the calling thread instead.
+    jobs: Option<mpsc::Sender<LoadJob>>,
+    results_sender: mpsc::Sender<LoadResult>,
+    results: mpsc::Receiver<LoadResult>,
+    states: KelpMap<KelpLoadId, LoadState>,
+    next_id: u32,
+    progress: LoadProgress,
+}
+
+impl Default for TextureLoader {
+    fn default() -> Self {
+        let (results_sender, results) = mpsc::channel();
+        Self {
+            jobs: None,
+            results_sender,
+            results,
+            states: KelpMap::default(),
+            next_id: 0,
+            progress: LoadProgress::default(),
+        }
+    }
+}
+
+impl TextureLoader {
+    pub fn queue(&mut self, source: LoadSource) -> KelpLoadId {
+        let id = KelpLoadId(self.next_id);
+        self.next_id += 1;
+        // Progress counts from when the last group of loads finished
+        if self.progress.loaded == self.progress.total {
+            self.progress = LoadProgress::default();
+        }
+        self.progress.total += 1;
+        self.states.insert(id, LoadState::Loading);
+        if self.jobs.is_none() {
+            self.jobs = start_workers(&self.results_sender);
+        }
+        let job = match &self.jobs {
+            Some(jobs) => jobs.send((id, source)).err().map(|mpsc::SendError(job)| job),
+            None => Some((id, source)),
+        };
+        // Without any workers to take it, decode the image now so that the load still finishes
+        if let Some((id, source)) = job {
+            _ = self.results_sender.send((id, load(source)));
+        }
+        id
+    }
+
+    /// Take the images that have been decoded since the last call
+    pub fn take_decoded(&mut self) -> Vec<LoadResult> {
+        self.results.try_iter().collect()
+    }
+
+    /// Record the texture created from a decoded image, or the error in decoding or uploading it
+    pub fn finish(&mut self, id: KelpLoadId, texture: Result<KelpTextureId, KelpError>) {
+        let state = match texture {
+            Ok(texture) => LoadState::Ready(texture),
+            Err(err) => LoadState::Failed(err),
+        };
+        self.states.insert(id, state);
+        self.progress.loaded += 1;
+    }
+
+    pub fn state(&mut self, id: KelpLoadId) -> Result<Option<KelpTextureId>, KelpError> {
+        match self.states.get(&id) {
+            Some(LoadState::Loading) => Ok(None),
+            Some(LoadState::Ready(_) | LoadState::Failed(_)) => match self.states.swap_remove(&id) {
+                Some(LoadState::Ready(texture)) => Ok(Some(texture)),
+                Some(LoadState::Failed(err)) => Err(err),
+                _ => unreachable!(),
+            },
+            None => Err(KelpError::InvalidLoadId),
+        }
+    }
+
+    pub fn progress(&self) -> LoadProgress {
+        self.progress
+    }
+}
+
+/// Start the worker threads, which stop once the returned sender is dropped, or `None` if none could be started
+fn start_workers(results: &mpsc::Sender<LoadResult>) -> Option<mpsc::Sender<LoadJob>> {
+    let (jobs, receiver) = mpsc::channel::<LoadJob>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let count = thread::available_parallelism().map_or(1, |count| count.get().min(MAX_WORKERS));
+    let mut started = 0;
+    for index in 0..count {
+        let receiver = receiver.clone();
+        let results = results.clone();
+        let worker = move || loop {
+            // The lock is only held while waiting, so other workers can take jobs while this one decodes
+            let job = receiver.lock().unwrap().recv();
+            let Ok((id, source)) = job else {
+                break;
+            };
+            _ = results.send((id, load(source)));
+        };
+        let spawned = thread::Builder::new().name(format!("kelp texture loader {index}")).spawn(worker);
+        started += spawned.is_ok() as usize;
+    }
+    (started > 0).then_some(jobs)
+}
+
+/// Read and decode an image, turning a panic in a decoder into an error so the load still finishes
+fn load(source: LoadSource) -> Result<DecodedImage, KelpError> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        match source {
+            LoadSource::Path(path) => std::fs::read(path).map_err(KelpError::from),
+            LoadSource::Memory(data) => Ok(data),
+        }
+        .and_then(|data| decode_image(&data))
+    }))
+    .unwrap_or_else(|_| Err(KelpError::ImageError("decoder panicked".to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wait for the workers to decode the given number of images
+    fn decoded(loader: &mut TextureLoader, count: usize) -> Vec<LoadResult> {
+        let mut results = Vec::new();
+        while results.len() < count {
+            results.extend(loader.take_decoded());
+            thread::yield_now();
+        }
+        results
+    }
+
+    #[test]
+    fn reports_failed_loads_once() {
+        let mut loader = TextureLoader::default();
+        let id = loader.queue(LoadSource::Memory(b"not an image".to_vec()));
+        assert!(matches!(loader.state(id), Ok(None)));
+        let (decoded_id, result) = decoded(&mut loader, 1).remove(0);
+        assert_eq!(decoded_id, id);
+        loader.finish(id, result.map(|_| KelpTextureId { layer: 0, index: 0 }));
+        assert!(matches!(loader.state(id), Err(KelpError::UnsupportedImage(_))));
+        assert!(matches!(loader.state(id), Err(KelpError::InvalidLoadId)));
+    }
+
+    #[test]
+    fn releases_finished_loads_once_returned() {
+        let mut loader = TextureLoader::default();
+        let texture = KelpTextureId { layer: 0, index: 3 };
+        let id = loader.queue(LoadSource::Memory(Vec::new()));
+        decoded(&mut loader, 1);
+        loader.finish(id, Ok(texture));
+        assert_eq!(loader.state(id).unwrap(), Some(texture));
+        assert!(matches!(loader.state(id), Err(KelpError::InvalidLoadId)));
+        assert_eq!(loader.progress(), LoadProgress { loaded: 1, total: 1 });
+    }
+}
@@ -1,3 +1,4 @@
+use crate::AnimationClip;
 use bytemuck::{Pod, Zeroable};
 use glam::{Mat4, Vec4};
 use indexmap::IndexMap;
@@ -29,11 +30,201 @@ pub struct KelpColor {
     pub a: f32,
 }
 
+/// A rectangle in pixels, from its top left corner
+#[ffi_type]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(C)]
+pub struct KelpRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Which axes a sprite's texture is mirrored along
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum SpriteFlip {
+    #[default]
+    None = 0,
+    Horizontal = 1,
+    Vertical = 2,
+    Both = 3,
+}
+
+impl SpriteFlip {
+    pub fn horizontal(self) -> bool {
+        matches!(self, SpriteFlip::Horizontal | SpriteFlip::Both)
+    }
+
+    pub fn vertical(self) -> bool {
+        matches!(self, SpriteFlip::Vertical | SpriteFlip::Both)
+    }
+}
+
+/// How to slice a sprite sheet into equally sized frames, numbered along each row from the top left
+#[derive(Debug, Clone, Copy)]
+pub struct SheetGrid {
+    pub frame_width: u32,
+    pub frame_height: u32,
+    /// Pixels around the edge of the sheet before the first frame
+    pub margin: u32,
+    /// Pixels between neighbouring frames
+    pub spacing: u32,
+    /// Stop after this many frames, for sheets with a partly empty last row
+    pub count: Option<u32>,
+    /// Pivot of every frame, in pixels from its top left
+    pub pivot: mint::Vector2<f32>,
+}
+
+/// A named region of a sprite sheet, which may have been trimmed of transparent edges
+#[derive(Debug, Clone)]
+pub struct SpriteFrame {
+    pub name: String,
+    pub region: KelpRegionId,
+    /// Position of the trimmed region within the untrimmed sprite
+    pub offset: mint::Vector2<f32>,
+    /// Size of the trimmed region
+    pub size: mint::Vector2<f32>,
+    /// Size of the sprite before trimming
+    pub source_size: mint::Vector2<f32>,
+    /// Pivot in pixels from the top left of the untrimmed sprite
+    pub pivot: mint::Vector2<f32>,
+    /// Frame duration in seconds, if the sheet has one
+    pub duration: Option<f32>,
+}
+
+/// A texture sliced into frames
+#[derive(Debug, Clone)]
+pub struct SpriteSheet {
+    pub texture: KelpTextureId,
+    pub frames: Vec<SpriteFrame>,
+    /// Animations tagged in the sheet, only exported by Aseprite
+    pub clips: Vec<AnimationClip>,
+}
+
+/// How the parts of a nine-slice between its corners fill their space
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum NineSliceFill {
+    #[default]
+    Stretch = 0,
+    /// Repeat at the source size, cutting the last repeat short
+    Tile = 1,
+}
+
+/// A region split by border insets into corners that keep their size, and edges and a centre that fill the rest
+#[derive(Debug, Clone, Copy)]
+pub struct NineSlice {
+    pub region: KelpRegionId,
+    /// Border insets in pixels from each side of the region
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub edges: NineSliceFill,
+    pub center: NineSliceFill,
+}
+
+/// A 2D transform for sprites, applied as flip and scale, then skew, then rotation around the origin
+#[ffi_type]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct Transform2D {
+    pub x: f32,
+    pub y: f32,
+    /// Clockwise, in radians
+    pub rotation: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    /// The pivot in pixels from the top left of the sprite, which is placed at the position
+    pub origin_x: f32,
+    pub origin_y: f32,
+    /// Shear angles in radians, of the horizontal and vertical axes
+    pub skew_x: f32,
+    pub skew_y: f32,
+    /// Mirrors the sprite around its origin
+    pub flip: SpriteFlip,
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            rotation: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            origin_x: 0.0,
+            origin_y: 0.0,
+            skew_x: 0.0,
+            skew_y: 0.0,
+            flip: SpriteFlip::None,
+        }
+    }
+}
+
+impl Transform2D {
+    /// The `InstanceData::world` matrix for a sprite of this size in pixels
+    pub fn world(&self, width: f32, height: f32) -> mint::RowMatrix3x2<f32> {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (tan_x, tan_y) = (self.skew_x.tan(), self.skew_y.tan());
+        let scale_x = if self.flip.horizontal() {
+            -self.scale_x
+        } else {
+            self.scale_x
+        };
+        let scale_y = if self.flip.vertical() {
+            -self.scale_y
+        } else {
+            self.scale_y
+        };
+        // Columns of rotation * skew * scale
+        let col_1 = [scale_x * (cos - sin * tan_y), scale_x * (sin + cos * tan_y)];
+        let col_2 = [scale_y * (cos * tan_x - sin), scale_y * (sin * tan_x + cos)];
+        mint::RowMatrix3x2 {
+            x: mint::Vector2 { x: col_1[0] * width, y: col_1[1] * width },
+            y: mint::Vector2 { x: col_2[0] * height, y: col_2[1] * height },
+            z: mint::Vector2 {
+                x: self.x - col_1[0] * self.origin_x - col_2[0] * self.origin_y,
+                y: self.y - col_1[1] * self.origin_x - col_2[1] * self.origin_y,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
 #[repr(C)]
 pub struct KelpTextureId {
     pub(crate) layer: u32,
-    pub(crate) alloc_id: guillotiere::AllocId,
+    /// Numbers textures in the order they were created, so ids stay the same when the atlas is compacted
+    pub(crate) index: u32,
+}
+
+impl KelpTextureId {
+    /// Marks textures too large for the atlas, which have a texture of their own
+    pub(crate) const DEDICATED_LAYER: u32 = u32::MAX;
+    /// Marks indexed textures, which are in the single channel index atlas
+    pub(crate) const INDEXED_LAYER: u32 = u32::MAX - 1;
+
+    pub(crate) fn is_dedicated(&self) -> bool {
+        self.layer == Self::DEDICATED_LAYER
+    }
+
+    pub(crate) fn is_indexed(&self) -> bool {
+        self.layer == Self::INDEXED_LAYER
+    }
+
+    /// The layer to sample, in the atlas array or the single layer of a dedicated texture or the index atlas
+    pub(crate) fn array_layer(&self) -> u32 {
+        if self.is_dedicated() || self.is_indexed() {
+            0
+        } else {
+            self.layer
+        }
+    }
 }
 
 unsafe impl CTypeInfo for KelpTextureId {
@@ -42,6 +233,17 @@ unsafe impl CTypeInfo for KelpTextureId {
     }
 }
 
+/// A sub-rectangle of a texture, such as a frame of a sprite sheet
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct KelpRegionId(pub(crate) u32);
+
+unsafe impl CTypeInfo for KelpRegionId {
+    fn type_info() -> CType {
+        CType::Primitive(PrimitiveType::U32)
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
 #[repr(transparent)]
 pub struct KelpTargetId(pub(crate) wgpu::Id<wgpu::Texture>);
@@ -52,6 +254,98 @@ unsafe impl CTypeInfo for KelpTargetId {
     }
 }
 
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct KelpShaderId(pub(crate) wgpu::Id<wgpu::ShaderModule>);
+
+unsafe impl CTypeInfo for KelpShaderId {
+    fn type_info() -> CType {
+        CType::Primitive(PrimitiveType::U64)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct KelpLutId(pub(crate) wgpu::Id<wgpu::Texture>);
+
+unsafe impl CTypeInfo for KelpLutId {
+    fn type_info() -> CType {
+        CType::Primitive(PrimitiveType::U64)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct KelpPaletteId(pub(crate) u32);
+
+unsafe impl CTypeInfo for KelpPaletteId {
+    fn type_info() -> CType {
+        CType::Primitive(PrimitiveType::U32)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct KelpFontId(pub(crate) u32);
+
+unsafe impl CTypeInfo for KelpFontId {
+    fn type_info() -> CType {
+        CType::Primitive(PrimitiveType::U32)
+    }
+}
+
+/// How much compacting the atlas helped, measured by the largest free rectangle in any atlas layer
+#[ffi_type]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(C)]
+pub struct AtlasCompaction {
+    /// Textures moved to a new position
+    pub moved: u32,
+    /// Textures left where they were, as their layer no longer fit or moving them would lose mip levels
+    pub skipped: u32,
+    /// Area of the largest free rectangle before compacting, in pixels
+    pub largest_free_before: u32,
+    /// Area of the largest free rectangle after compacting, in pixels
+    pub largest_free_after: u32,
+}
+
+impl AtlasCompaction {
+    /// Area in pixels added to the largest free rectangle
+    pub fn reclaimed(&self) -> u32 {
+        self.largest_free_after.saturating_sub(self.largest_free_before)
+    }
+}
+
+/// A texture loading in the background, from `Kelp::load_texture_async`
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct KelpLoadId(pub(crate) u32);
+
+unsafe impl CTypeInfo for KelpLoadId {
+    fn type_info() -> CType {
+        CType::Primitive(PrimitiveType::U32)
+    }
+}
+
+/// Progress of background texture loads, for loading screens
+#[ffi_type]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(C)]
+pub struct LoadProgress {
+    pub loaded: u32,
+    pub total: u32,
+}
+
+impl LoadProgress {
+    /// The fraction of loads that have finished, which is 1 when there are none
+    pub fn fraction(&self) -> f32 {
+        match self.total {
+            0 => 1.0,
+            total => self.loaded as f32 / total as f32,
+        }
+    }
+}
+
 #[ffi_type]
 #[derive(Debug)]
 #[repr(C)]
@@ -64,6 +358,29 @@ pub struct Camera {
     pub scale: f32,
 }
 
+/// How the low resolution canvas is scaled up to fit the window surface
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum CanvasScaling {
+    /// Largest whole number multiple of the canvas size that fits, for pixel-perfect output
+    Integer = 0,
+    /// Largest scale that fits while keeping the aspect ratio
+    Fit = 1,
+}
+
+/// A fixed size virtual canvas that replaces the window surface as the default render target
+#[ffi_type]
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct CanvasConfig {
+    pub width: u32,
+    pub height: u32,
+    pub scaling: CanvasScaling,
+    /// Colour of the letterbox bars around the scaled canvas
+    pub bars: KelpColor,
+}
+
 #[ffi_type]
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
@@ -71,6 +388,117 @@ pub enum InstanceMode {
     Multiply = 1,
     Wash = 2,
     Veto = 3,
+    /// Desaturates towards luminance, `effect.x` is the amount from 0 to 1
+    Grayscale = 4,
+    /// Inverts the colour channels, `effect.x` is the amount from 0 to 1
+    Invert = 5,
+    /// Adds the `effect.xyz` colour on top, scaled by the `effect.w` amount
+    Flash = 6,
+    /// Rotates the hue by `effect.x` radians, then multiplies saturation by `effect.y` and value by `effect.z`.
+    /// The multipliers are used as given, so leaving them at 0 draws black; `InstanceData::hue_shift` sets all three.
+    HueShift = 7,
+    /// Draws the `effect` colour on transparent pixels bordering opaque ones. The outline is only drawn within the
+    /// instance, so the image needs a transparent border where it should show, and the atlas needs padding from
+    /// `Kelp::set_atlas_padding` so that the edges of neighbouring textures are not outlined.
+    Outline = 8,
+    /// Single channel (red) signed distance field, such as text, smoothed to the on screen size.
+    /// `effect.x` is the distance range in texels the field was generated with, `effect.y` the outline
+    /// width in field units from 0 to 0.5, `effect.zw` the shadow offset in texels, and `effect_colors`
+    /// hold the outline and shadow colours
+    Sdf = 9,
+    /// Multi-channel signed distance field, with the same parameters as `Sdf`
+    Msdf = 10,
+}
+
+/// The layout of each pixel in uploaded texture data
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+#[repr(u8)]
+pub enum PixelFormat {
+    #[default]
+    Rgba8 = 0,
+    Bgra8 = 1,
+    Rgb8 = 2,
+    Gray8 = 3,
+    GrayAlpha8 = 4,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> u32 {
+        match self {
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => 4,
+            PixelFormat::Rgb8 => 3,
+            PixelFormat::Gray8 => 1,
+            PixelFormat::GrayAlpha8 => 2,
+        }
+    }
+}
+
+/// Whether uploaded colours are already multiplied by their alpha
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+#[repr(u8)]
+pub enum AlphaMode {
+    #[default]
+    Straight = 0,
+    Premultiplied = 1,
+}
+
+/// How uploaded texture data is laid out, converted to straight alpha RGBA on upload
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+#[repr(C)]
+pub struct UploadFormat {
+    pub pixels: PixelFormat,
+    pub alpha: AlphaMode,
+}
+
+/// Whether a texture holds sRGB encoded colours, or linear values such as normals or masks that are sampled as is
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+#[repr(u8)]
+pub enum ColorSpace {
+    #[default]
+    Srgb = 0,
+    Linear = 1,
+}
+
+/// Image file formats that textures can be loaded from, each supported when its `image-*` feature is enabled
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ImageFormat {
+    Png,
+    Qoi,
+    Jpeg,
+}
+
+impl ImageFormat {
+    /// Detect the format of an image file from its signature
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if data.starts_with(b"qoif") {
+            Some(ImageFormat::Qoi)
+        } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageFormat::Jpeg)
+        } else {
+            None
+        }
+    }
+}
+
+/// How an instance samples beyond the edges of its texture or region, such as with a source scale above 1
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum WrapMode {
+    /// Sample the atlas as is, which shows neighbouring allocations past the edges
+    #[default]
+    None = 0,
+    /// Repeat the edge pixels
+    Clamp = 1,
+    Repeat = 2,
+    /// Repeat, mirroring every other repeat
+    Mirror = 3,
 }
 
 #[derive(Debug)]
@@ -81,6 +509,61 @@ pub struct InstanceData {
     pub source_trans: mint::Vector2<f32>,
     pub source_scale: mint::Vector2<f32>,
     pub world: mint::RowMatrix3x2<f32>,
+    /// Draw an indexed texture with the colours of this palette
+    pub palette: Option<KelpPaletteId>,
+    /// Parameters of the effect selected by the instance mode
+    pub effect: mint::Vector4<f32>,
+    /// Additional colours of the effect selected by the instance mode
+    pub effect_colors: [mint::Vector4<f32>; 2],
+    /// Draw from this region instead of the whole texture, with the source transform relative to it
+    pub region: Option<KelpRegionId>,
+    /// How to sample outside the texture or region
+    pub wrap: WrapMode,
+    /// Blend between mip levels when drawn smaller, if drawn smooth from a texture created with mipmaps
+    pub mipmaps: bool,
+}
+
+impl Default for InstanceData {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0, 1.0].into(),
+            mode: InstanceMode::Multiply,
+            source_trans: [0.0, 0.0].into(),
+            source_scale: [1.0, 1.0].into(),
+            world: mint::RowMatrix3x2 {
+                x: mint::Vector2 { x: 1.0, y: 0.0 },
+                y: mint::Vector2 { x: 0.0, y: 1.0 },
+                z: mint::Vector2 { x: 0.0, y: 0.0 },
+            },
+            palette: None,
+            effect: [0.0, 0.0, 0.0, 0.0].into(),
+            effect_colors: [[0.0, 0.0, 0.0, 0.0].into(); 2],
+            region: None,
+            wrap: WrapMode::None,
+            mipmaps: false,
+        }
+    }
+}
+
+impl InstanceData {
+    /// An instance that rotates the hue by `radians` and multiplies saturation and value, where 1 leaves them as
+    /// they are
+    pub fn hue_shift(radians: f32, saturation: f32, value: f32) -> Self {
+        Self {
+            mode: InstanceMode::HueShift,
+            effect: [radians, saturation, value, 0.0].into(),
+            ..Default::default()
+        }
+    }
+
+    /// An instance outlined in a colour
+    pub fn outline(color: mint::Vector4<f32>) -> Self {
+        Self {
+            mode: InstanceMode::Outline,
+            effect: color,
+            ..Default::default()
+        }
+    }
 }
 
 impl From<InstanceMode> for [f32; 4] {
@@ -89,6 +572,153 @@ impl From<InstanceMode> for [f32; 4] {
             InstanceMode::Multiply => [1.0, 0.0, 0.0, 0.0],
             InstanceMode::Wash => [0.0, 1.0, 0.0, 0.0],
             InstanceMode::Veto => [0.0, 0.0, 1.0, 0.0],
+            // Effects are applied over multiply, selected by the last component
+            InstanceMode::Grayscale => [1.0, 0.0, 0.0, 1.0],
+            InstanceMode::Invert => [1.0, 0.0, 0.0, 2.0],
+            InstanceMode::Flash => [1.0, 0.0, 0.0, 3.0],
+            InstanceMode::HueShift => [1.0, 0.0, 0.0, 4.0],
+            InstanceMode::Outline => [1.0, 0.0, 0.0, 5.0],
+            InstanceMode::Sdf => [1.0, 0.0, 0.0, 6.0],
+            InstanceMode::Msdf => [1.0, 0.0, 0.0, 7.0],
+        }
+    }
+}
+
+/// How colours between palette entries are approximated when quantising
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum DitherMode {
+    None = 0,
+    /// A repeating 4x4 Bayer threshold pattern
+    Ordered = 1,
+    /// Each pixel takes on the error of its already visited neighbours, similar to Floyd-Steinberg
+    Diffusion = 2,
+}
+
+/// A full screen effect applied to the default render target before it is presented
+#[derive(Debug, Clone, Copy)]
+pub enum PostEffect {
+    /// Gaussian blur with a radius in pixels, up to 32
+    Blur { radius: f32 },
+    /// Blurs the areas brighter than the threshold and adds them back over the image
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+        radius: f32,
+    },
+    /// Darkens towards the edges, starting at a radius from 0 (centre) to 1 (corners)
+    Vignette { intensity: f32, radius: f32, softness: f32 },
+    /// Basic colour adjustments, which leave the image unchanged when all 1
+    ColorGrade {
+        brightness: f32,
+        contrast: f32,
+        saturation: f32,
+    },
+    /// Darkened horizontal lines, with a spacing in pixels
+    Scanlines { intensity: f32, spacing: f32 },
+    /// Splits the red and blue channels outwards, by up to the offset in pixels at the edges
+    ChromaticAberration { offset: f32 },
+    /// Colour grading through a LUT from `Kelp::create_lut`, mixed in by the intensity
+    Lut { lut: KelpLutId, intensity: f32 },
+    /// Maps every pixel to the nearest colour of a palette from `Kelp::create_palette`
+    Quantize {
+        palette: KelpPaletteId,
+        dither: DitherMode,
+        dither_strength: f32,
+    },
+    /// A user shader from `Kelp::create_post_shader`, with its parameters
+    Custom { shader: KelpShaderId, params: [f32; 8] },
+}
+
+/// Which `PostEffect` a `PostEffectDesc` describes
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum PostEffectKind {
+    Blur = 0,
+    Bloom = 1,
+    Vignette = 2,
+    ColorGrade = 3,
+    Scanlines = 4,
+    ChromaticAberration = 5,
+    Lut = 6,
+    Quantize = 7,
+    Custom = 8,
+}
+
+/// An FFI safe `PostEffect`, turned into one with `Kelp::post_effect_from_desc`. The parameters are the effect's
+/// numeric fields in the order they are declared, with the dither mode of `Quantize` as its number, and `id` is the
+/// LUT, palette or shader the effect uses.
+#[ffi_type]
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct PostEffectDesc {
+    pub kind: PostEffectKind,
+    pub id: u64,
+    pub params: [f32; 8],
+}
+
+/// How each line of text is placed horizontally
+#[ffi_type]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum TextAlign {
+    #[default]
+    Left = 0,
+    Center = 1,
+    Right = 2,
+    /// Spreads the words of wrapped lines to fill the maximum width, leaving the last line of paragraphs left aligned
+    Justify = 3,
+}
+
+/// Options for laying out text
+#[derive(Debug, Clone, Copy)]
+pub struct TextLayout {
+    /// Wrap lines between words to fit this width, with words wider than it overflowing on their own line
+    pub max_width: Option<f32>,
+    pub align: TextAlign,
+    /// Multiplier of the font's line spacing, at the largest size on each line
+    pub line_height: f32,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self { max_width: None, align: TextAlign::Left, line_height: 1.0 }
+    }
+}
+
+/// A run of text with its own colour and size in pixels
+#[derive(Debug, Clone, Copy)]
+pub struct TextSpan<'a> {
+    pub text: &'a str,
+    pub color: mint::Vector4<f32>,
+    pub size: f32,
+}
+
+/// Positioned glyph instances, grouped by the atlas texture their glyphs are drawn from
+#[derive(Debug)]
+pub struct TextInstances {
+    pub pages: Vec<(KelpTextureId, Vec<InstanceData>)>,
+    /// Whether the glyphs are drawn with linear filtering, which bitmap fonts are not
+    pub smooth: bool,
+    /// Measured width of the laid out text, from its top left
+    pub width: f32,
+    /// Measured height of the laid out text, from its top left
+    pub height: f32,
+}
+
+impl Default for TextInstances {
+    fn default() -> Self {
+        Self { pages: Vec::new(), smooth: true, width: 0.0, height: 0.0 }
+    }
+}
+
+impl TextInstances {
+    pub(crate) fn push(&mut self, texture: KelpTextureId, instance: InstanceData) {
+        match self.pages.iter_mut().find(|(page, _)| *page == texture) {
+            Some((_, instances)) => instances.push(instance),
+            None => self.pages.push((texture, vec![instance])),
         }
     }
 }
@@ -100,19 +730,27 @@ impl From<InstanceMode> for [f32; 4] {
 pub struct InstanceBatch {
     pub blend_mode: BlendMode,
     pub instance_count: u32,
+    /// The texture drawn from, which is bound instead of the atlas if it is too large to be in it
+    pub texture: KelpTextureId,
 }
 
+#[ffi_type]
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct InstanceGPU {
     pub color: [f32; 4],
     pub mode: [f32; 4],
-    pub layer_smooth: [f32; 2],
+    pub sampling: [f32; 4],
+    pub effect: [f32; 4],
+    pub effect_color_1: [f32; 4],
+    pub effect_color_2: [f32; 4],
+    pub bounds: [f32; 4],
     pub source_trans: [f32; 2],
     pub source_scale: [f32; 2],
     pub world_col_1: [f32; 2],
     pub world_col_2: [f32; 2],
     pub world_trans: [f32; 2],
+    pub texture_info: [f32; 2],
 }
 
 #[repr(transparent)]
@@ -126,12 +764,64 @@ pub enum KelpError {
     SwapchainError(#[from] wgpu::SurfaceError),
     #[error("Invalid texture id")]
     InvalidTextureId,
+    #[error("Invalid region id")]
+    InvalidRegionId,
+    #[error("Region is outside the bounds of its texture")]
+    RegionOutOfBounds,
+    #[error("Failed to load sprite sheet: {0}")]
+    SheetError(String),
+    #[error("Nine-slice insets do not fit inside its region")]
+    InvalidNineSlice,
+    #[error("No space left in the texture atlas")]
+    AtlasFull,
+    #[error("Instances can only use regions of a dedicated texture when drawn with that texture")]
+    RegionTextureMismatch,
+    #[error("Texture data is {actual} bytes, but the update needs {expected}")]
+    TextureDataSize { expected: usize, actual: usize },
+    #[error("Texture data rows are shorter than the update width")]
+    InvalidRowStride,
+    #[error("Unsupported image: {0}")]
+    UnsupportedImage(String),
+    #[error("Invalid load id")]
+    InvalidLoadId,
+    #[error("Cannot compact the atlas while a frame is being drawn")]
+    FrameInProgress,
+    #[error("Texture of {width}x{height} is larger than the device maximum of {max}")]
+    TextureTooLarge { width: u32, height: u32, max: u32 },
+    #[error("Textures must be at least one pixel wide and high")]
+    EmptyTexture,
+    #[error("Textures with mipmaps can only be updated whole")]
+    PartialMipmapUpdate,
+    #[error("Indexed textures can only be updated with one byte indices, as PixelFormat::Gray8")]
+    IndexedTextureFormat,
     #[error("Invalid target id")]
     InvalidTargetId,
     #[error("Invalid bind group id")]
     InvalidBindGroupId,
     #[error("Invalid pipeline id")]
     InvalidPipelineId,
+    #[error("Invalid shader id")]
+    InvalidShaderId,
+    #[error("Failed to compile shader: {0}")]
+    ShaderError(String),
+    #[error("Invalid LUT id")]
+    InvalidLutId,
+    #[error("Invalid palette id")]
+    InvalidPaletteId,
+    #[error("No more palettes can be created")]
+    TooManyPalettes,
+    #[error("Data length does not match the given dimensions")]
+    InvalidDataLength,
+    #[error("Invalid font id")]
+    InvalidFontId,
+    #[error("Failed to load font: {0}")]
+    FontError(String),
+    #[error("Glyph is too large to fit in the atlas")]
+    GlyphTooLarge,
+    #[error("Failed to decode image: {0}")]
+    ImageError(String),
+    #[error("Failed to read file")]
+    IoError(#[from] std::io::Error),
     #[error("Failed to find an appropriate adapter")]
     NoAdapter,
     #[error("Failed to find an appropriate device")]
@@ -174,3 +864,94 @@ impl From<&Camera> for Mat4 {
 
 unsafe impl Zeroable for InstanceGPU {}
 unsafe impl Pod for InstanceGPU {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    /// Where a point in pixels from the top left of a sprite of this size is drawn
+    fn apply(world: &mint::RowMatrix3x2<f32>, (width, height): (f32, f32), (x, y): (f32, f32)) -> (f32, f32) {
+        let (u, v) = (x / width, y / height);
+        (world.z.x + u * world.x.x + v * world.y.x, world.z.y + u * world.x.y + v * world.y.y)
+    }
+
+    fn assert_near((x, y): (f32, f32), (expected_x, expected_y): (f32, f32)) {
+        assert!(
+            (x - expected_x).abs() < 1e-4 && (y - expected_y).abs() < 1e-4,
+            "({x}, {y}) != ({expected_x}, {expected_y})"
+        );
+    }
+
+    #[test]
+    fn places_the_origin_at_the_position() {
+        let transform = Transform2D {
+            x: 100.0,
+            y: 50.0,
+            origin_x: 8.0,
+            origin_y: 8.0,
+            ..Default::default()
+        };
+        let world = transform.world(16.0, 8.0);
+        assert_near(apply(&world, (16.0, 8.0), (8.0, 8.0)), (100.0, 50.0));
+        assert_near(apply(&world, (16.0, 8.0), (0.0, 0.0)), (92.0, 42.0));
+        assert_near(apply(&world, (16.0, 8.0), (16.0, 8.0)), (108.0, 50.0));
+    }
+
+    #[test]
+    fn rotates_and_scales_around_the_origin() {
+        let transform = Transform2D {
+            x: 10.0,
+            y: 10.0,
+            rotation: FRAC_PI_2,
+            scale_x: 2.0,
+            scale_y: 3.0,
+            origin_x: 4.0,
+            origin_y: 2.0,
+            ..Default::default()
+        };
+        let world = transform.world(8.0, 4.0);
+        assert_near(apply(&world, (8.0, 4.0), (4.0, 2.0)), (10.0, 10.0));
+        // Clockwise with y down, so the sprite's right points down the screen
+        assert_near(apply(&world, (8.0, 4.0), (8.0, 2.0)), (10.0, 18.0));
+        assert_near(apply(&world, (8.0, 4.0), (4.0, 4.0)), (4.0, 10.0));
+    }
+
+    #[test]
+    fn flips_around_the_origin() {
+        let flipped = |flip| Transform2D {
+            x: 20.0,
+            y: 20.0,
+            origin_x: 2.0,
+            origin_y: 1.0,
+            flip,
+            ..Default::default()
+        };
+        let world = flipped(SpriteFlip::Horizontal).world(8.0, 4.0);
+        assert_near(apply(&world, (8.0, 4.0), (2.0, 1.0)), (20.0, 20.0));
+        assert_near(apply(&world, (8.0, 4.0), (0.0, 0.0)), (22.0, 19.0));
+        let world = flipped(SpriteFlip::Both).world(8.0, 4.0);
+        assert_near(apply(&world, (8.0, 4.0), (2.0, 1.0)), (20.0, 20.0));
+        assert_near(apply(&world, (8.0, 4.0), (0.0, 0.0)), (22.0, 21.0));
+    }
+
+    #[test]
+    fn skews_each_axis() {
+        let skew_x = Transform2D { skew_x: FRAC_PI_2 / 2.0, ..Default::default() }.world(16.0, 8.0);
+        assert_near((skew_x.x.x, skew_x.x.y), (16.0, 0.0));
+        assert_near((skew_x.y.x, skew_x.y.y), (8.0, 8.0));
+        let skew_y = Transform2D { skew_y: FRAC_PI_2 / 2.0, ..Default::default() }.world(16.0, 8.0);
+        assert_near((skew_y.x.x, skew_y.x.y), (16.0, 16.0));
+        assert_near((skew_y.y.x, skew_y.y.y), (0.0, 8.0));
+    }
+
+    #[test]
+    fn detects_image_formats_from_their_signature() {
+        assert_eq!(ImageFormat::detect(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::detect(b"qoif\0\0\0\x01"), Some(ImageFormat::Qoi));
+        assert_eq!(ImageFormat::detect(&[0xFF, 0xD8, 0xFF, 0xE0]), Some(ImageFormat::Jpeg));
+        assert_eq!(ImageFormat::detect(b"\x89PNG"), None);
+        assert_eq!(ImageFormat::detect(b"GIF89a"), None);
+        assert_eq!(ImageFormat::detect(&[]), None);
+    }
+}